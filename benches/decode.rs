@@ -0,0 +1,73 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use rougestein::wolf3d::{self, MapArchive, MapData, Plane, compression::PlaneDecoder};
+
+const MAPS: usize = 60;
+
+// Rooms of floor codes walled in by a handful of wall types, with scattered objects.
+fn test_archive() -> MapArchive {
+    let maps = (0..MAPS)
+        .map(|i| {
+            let plane = |f: &dyn Fn(usize, usize) -> u16| {
+                let cells = (0..64 * 64).map(|x| f(x % 64, x / 64)).collect();
+                Plane::from_vec(64, 64, cells).unwrap()
+            };
+            MapData::new(
+                plane(&|x, y| {
+                    if x % 8 == 0 || y % 8 == 0 {
+                        1 + ((x / 8 + y / 8 + i) % 6) as u16
+                    } else {
+                        0x6C + ((x / 16 + y / 16) % 4) as u16
+                    }
+                }),
+                plane(&|x, y| {
+                    if (x * 7 + y * 13 + i) % 37 == 0 {
+                        23 + (x % 5) as u16
+                    } else {
+                        0
+                    }
+                }),
+                plane(&|_, _| 0),
+                64,
+                64,
+                format!("Map {}", i),
+            )
+        })
+        .collect::<Vec<MapData>>();
+
+    let mut maphead = vec![];
+    let mut gamemaps = vec![];
+    wolf3d::write_gamemaps_to(&mut maphead, &mut gamemaps, &maps).unwrap();
+    MapArchive::from_bytes(&maphead, &gamemaps).unwrap()
+}
+
+fn decode(c: &mut Criterion) {
+    let archive = test_archive();
+
+    c.bench_function("decode planes into vectors", |b| {
+        b.iter(|| {
+            for i in 0..MAPS {
+                for plane in 0..3 {
+                    std::hint::black_box(archive.plane(i, plane).unwrap());
+                }
+            }
+        })
+    });
+
+    let mut decoder = PlaneDecoder::new();
+    let mut out = vec![0; 64 * 64];
+    c.bench_function("decode planes into a reused buffer", |b| {
+        b.iter(|| {
+            for i in 0..MAPS {
+                for plane in 0..3 {
+                    archive
+                        .plane_into(i, plane, &mut decoder, &mut out)
+                        .unwrap();
+                    std::hint::black_box(&out);
+                }
+            }
+        })
+    });
+}
+
+criterion_group!(benches, decode);
+criterion_main!(benches);
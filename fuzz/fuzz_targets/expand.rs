@@ -0,0 +1,21 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rougestein::wolf3d::{MapArchive, compression};
+
+// Any input must produce a result or an error, never a panic.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(expanded) = compression::carmack_expand(data) {
+        let _ = compression::rlew_expand(&expanded, 0xABCD);
+    }
+    let _ = compression::rlew_expand(data, 0xABCD);
+
+    // The first 402 bytes stand in for MAPHEAD, the rest for GAMEMAPS.
+    if data.len() > 402
+        && let Ok(archive) = MapArchive::from_bytes(&data[..402], &data[402..])
+    {
+        for i in 0..archive.len() {
+            let _ = archive.map(i);
+        }
+    }
+});
@@ -0,0 +1,559 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::process::ExitCode;
+
+use rougestein::wolf3d::diff::MapDiff;
+use rougestein::wolf3d::image::Image;
+use rougestein::wolf3d::stats::MapStats;
+use rougestein::wolf3d::tiles::{Difficulty, EnemyKind};
+use rougestein::wolf3d::validate::{Severity, validate};
+use rougestein::wolf3d::{self, MapArchive, MapData, MapFormat, Variant};
+
+const USAGE: &str = "\
+Usage: wolfmap [--data PATH] [--variant EXT] [--map-format F] <command> [arguments]
+
+Commands:
+  list                       list the maps in the data set
+  dump <map> [--format F]    print a map as text, emoji or raw numbers
+             [--plane N]     (raw only: print a single plane)
+  extract <map> <dir>        write each plane as little-endian words to <dir>
+  stats <map>                print the kill, secret and treasure totals and the
+        [--difficulty D]     pickups of a map on easy, medium or hard (default)
+  check [<map>...]           report problems that break maps, for every map by
+                             default; fails when any error is found
+  diff <old> <new>           list the tiles and objects that changed, and with
+       [--overlay FILE]      --overlay draw them over the new map (.ppm, .png)
+  convert <source>... <out>  convert maps between formats
+
+Maps are given by their index in `list`. Diff and convert also take PATH:INDEX
+for a map in another data set, and Tiled .tmj/.json/.tmx files. The convert
+output is chosen by extension (.tmj, .json, .tmx, .ppm, .png) or, for anything
+else, is a directory that receives a MAPHEAD/GAMEMAPS pair holding every source.
+
+Options:
+  --data PATH      data directory or zip archive (default: assets)
+  --variant EXT    data file extension such as WL6 (default: detected)
+  --map-format F   how planes are stored: carmack, rlew (MAPTEMP) or raw
+                   (default: detected)
+  -h, --help       show this help
+
+Exit codes: 0 on success, 1 when a command fails, 2 for bad usage.";
+
+// Bad command lines are reported with exit code 2, everything else with 1.
+#[derive(Debug)]
+struct Usage(String);
+
+impl fmt::Display for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for Usage {}
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+fn usage<T>(message: impl Into<String>) -> Result<T> {
+    Err(Box::new(Usage(message.into())))
+}
+
+#[derive(Debug)]
+struct Args {
+    data: String,
+    variant: Option<Variant>,
+    map_format: Option<MapFormat>,
+    format: Option<String>,
+    overlay: Option<String>,
+    plane: Option<usize>,
+    difficulty: Difficulty,
+    positional: Vec<String>,
+}
+
+impl Args {
+    fn parse(args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut result = Self {
+            data: "assets".to_string(),
+            variant: None,
+            map_format: None,
+            format: None,
+            overlay: None,
+            plane: None,
+            difficulty: Difficulty::Hard,
+            positional: vec![],
+        };
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| match args.next() {
+                Some(x) => Ok(x),
+                None => usage(format!("{} needs a value", name)),
+            };
+            match arg.as_str() {
+                "--data" => result.data = value("--data")?,
+                "--variant" => {
+                    let extension = value("--variant")?;
+                    result.variant = match Variant::from_extension(&extension) {
+                        Some(x) => Some(x),
+                        None => return usage(format!("unknown variant: {}", extension)),
+                    };
+                }
+                "--map-format" => {
+                    let format = value("--map-format")?;
+                    result.map_format = match MapFormat::from_name(&format) {
+                        Some(x) => Some(x),
+                        None => return usage(format!("unknown map format: {}", format)),
+                    };
+                }
+                "--format" => result.format = Some(value("--format")?),
+                "--overlay" => result.overlay = Some(value("--overlay")?),
+                "--plane" => {
+                    let plane = value("--plane")?;
+                    result.plane = match plane.parse() {
+                        Ok(x @ 0..=2) => Some(x),
+                        _ => return usage(format!("plane must be 0, 1 or 2, not {}", plane)),
+                    };
+                }
+                "--difficulty" => {
+                    let difficulty = value("--difficulty")?;
+                    result.difficulty = match difficulty.to_ascii_lowercase().as_str() {
+                        "easy" => Difficulty::Easy,
+                        "medium" => Difficulty::Medium,
+                        "hard" => Difficulty::Hard,
+                        _ => return usage(format!("unknown difficulty: {}", difficulty)),
+                    };
+                }
+                x if x.starts_with('-') && x.len() > 1 => {
+                    return usage(format!("unknown option: {}", x));
+                }
+                _ => result.positional.push(arg),
+            }
+        }
+
+        // Only raw dumps are laid out per plane; the other formats draw all of them at once.
+        if result.plane.is_some() && result.format.as_deref() != Some("raw") {
+            return usage("--plane only works with --format raw");
+        }
+
+        Ok(result)
+    }
+
+    fn variant(&self) -> Result<Variant> {
+        match self.variant {
+            Some(variant) => Ok(variant),
+            None => Ok(Variant::detect(&self.data)?),
+        }
+    }
+
+    fn archive(&self) -> Result<MapArchive> {
+        self.open(&self.data, self.variant()?)
+    }
+
+    fn open(&self, path: &str, variant: Variant) -> Result<MapArchive> {
+        let mut archive = MapArchive::open(path, variant)?;
+        if let Some(format) = self.map_format {
+            archive.set_format(format);
+        }
+        Ok(archive)
+    }
+
+    fn map(&self, index: &str) -> Result<MapData> {
+        Ok(self.archive()?.map(parse_index(index)?)?)
+    }
+
+    // A map index in the data set, PATH:INDEX for a map in another data set, or a Tiled file.
+    fn load(&self, source: &str) -> Result<MapData> {
+        if matches!(extension(source).as_deref(), Some("tmj" | "json" | "tmx")) {
+            return read_tiled(source);
+        }
+
+        match source.rsplit_once(':') {
+            Some((path, index)) => {
+                let variant = match self.variant {
+                    Some(variant) => variant,
+                    None => Variant::detect(path)?,
+                };
+                Ok(self.open(path, variant)?.map(parse_index(index)?)?)
+            }
+            None => self.map(source),
+        }
+    }
+
+    fn expect_arguments(&self, count: usize, names: &str) -> Result<()> {
+        if self.positional.len() != count + 1 {
+            return usage(format!("{} takes {}", self.positional[0], names));
+        }
+        Ok(())
+    }
+}
+
+fn parse_index(index: &str) -> Result<usize> {
+    match index.parse() {
+        Ok(x) => Ok(x),
+        Err(_) => usage(format!("not a map index: {}", index)),
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    }
+    if args.iter().any(|x| x == "-h" || x == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("wolfmap: {}", e);
+            if e.is::<Usage>() {
+                eprintln!("Try 'wolfmap --help' for more information.");
+                ExitCode::from(2)
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+    }
+}
+
+fn run(args: Vec<String>) -> Result<()> {
+    let args = Args::parse(args.into_iter())?;
+    let Some(command) = args.positional.first() else {
+        return usage("no command given");
+    };
+
+    match command.as_str() {
+        "list" => {
+            args.expect_arguments(0, "no arguments")?;
+            list(&args)
+        }
+        "dump" => {
+            args.expect_arguments(1, "a map index")?;
+            dump(&args, &args.positional[1])
+        }
+        "extract" => {
+            args.expect_arguments(2, "a map index and a directory")?;
+            extract(&args, &args.positional[1], &args.positional[2])
+        }
+        "stats" => {
+            args.expect_arguments(1, "a map index")?;
+            stats(
+                &args.map(&args.positional[1])?,
+                args.variant()?,
+                args.difficulty,
+            )
+        }
+        "diff" => {
+            args.expect_arguments(2, "an old and a new map")?;
+            diff(&args, &args.positional[1], &args.positional[2])
+        }
+        "check" => check(&args, &args.positional[1..]),
+        "convert" => {
+            if args.positional.len() < 3 {
+                return usage("convert takes one or more sources and an output");
+            }
+            let (output, sources) = args.positional[1..].split_last().unwrap();
+            convert(&args, sources, output)
+        }
+        x => usage(format!("unknown command: {}", x)),
+    }
+}
+
+fn list(args: &Args) -> Result<()> {
+    let archive = args.archive()?;
+    let mut out = io::stdout().lock();
+    writeln!(
+        out,
+        "{:>5} {:>4}  {:<16} {:>7}  {:>6} {:>6} {:>6}",
+        "index", "slot", "name", "size", "plane0", "plane1", "plane2"
+    )?;
+    for (index, info) in archive.infos().into_iter().enumerate() {
+        writeln!(
+            out,
+            "{:>5} {:>4}  {:<16} {:>7}  {:>6} {:>6} {:>6}",
+            index,
+            info.slot,
+            info.name,
+            format!("{}x{}", info.width, info.height),
+            info.plane_lengths[0],
+            info.plane_lengths[1],
+            info.plane_lengths[2]
+        )?;
+    }
+    Ok(())
+}
+
+fn dump(args: &Args, index: &str) -> Result<()> {
+    let map = args.map(index)?;
+    let mut out = io::stdout().lock();
+
+    match args.format.as_deref().unwrap_or("text") {
+        "text" => write!(out, "{}", map.to_text(args.variant()?))?,
+        "emoji" => write!(out, "{}", map.to_emoji(args.variant()?))?,
+        "raw" => {
+            let planes = [&map.plane0, &map.plane1, &map.plane2];
+            for (i, plane) in planes.into_iter().enumerate() {
+                if args.plane.is_some_and(|x| x != i) {
+                    continue;
+                }
+                if args.plane.is_none() {
+                    writeln!(out, "plane{}:", i)?;
+                }
+                for row in plane.rows() {
+                    let row: Vec<String> = row.iter().map(|x| format!("{:5}", x)).collect();
+                    writeln!(out, "{}", row.join(""))?;
+                }
+            }
+        }
+        x => return usage(format!("unknown dump format: {}", x)),
+    }
+    Ok(())
+}
+
+fn extract(args: &Args, index: &str, dir: &str) -> Result<()> {
+    let map = args.map(index)?;
+    fs::create_dir_all(dir)?;
+
+    for (i, plane) in [&map.plane0, &map.plane1, &map.plane2]
+        .into_iter()
+        .enumerate()
+    {
+        let path = Path::new(dir).join(format!("map{:02}-plane{}.bin", parse_index(index)?, i));
+        fs::write(&path, plane.to_bytes())?;
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+fn stats(map: &MapData, variant: Variant, difficulty: Difficulty) -> Result<()> {
+    let stats = MapStats::new(map, variant, difficulty);
+    let enemies: Vec<String> = [
+        EnemyKind::Guard,
+        EnemyKind::Officer,
+        EnemyKind::SS,
+        EnemyKind::Dog,
+        EnemyKind::Mutant,
+    ]
+    .into_iter()
+    .filter(|&x| stats.enemy_count(x) > 0)
+    .map(|x| format!("{} {:?}", stats.enemy_count(x), x))
+    .collect();
+
+    let kills = if enemies.is_empty() {
+        stats.kills().to_string()
+    } else {
+        format!("{} ({})", stats.kills(), enemies.join(", "))
+    };
+
+    let rows = [
+        ("name", map.name.clone()),
+        ("size", format!("{}x{}", map.width, map.height)),
+        ("difficulty", format!("{:?}", difficulty)),
+        ("kills", kills),
+        ("bosses", stats.bosses.to_string()),
+        ("secrets", stats.secrets.to_string()),
+        (
+            "treasures",
+            format!("{} ({} points)", stats.treasures, stats.treasure_score),
+        ),
+        (
+            "keys",
+            format!("{} gold, {} silver", stats.gold_keys, stats.silver_keys),
+        ),
+        ("locked doors", stats.locked_doors.to_string()),
+        ("ammo", stats.ammo.to_string()),
+        ("weapons", stats.weapons.to_string()),
+        ("health", stats.health.to_string()),
+        ("extra lives", stats.extra_lives.to_string()),
+        (
+            "floor",
+            format!("{} ({} reachable)", stats.floor, stats.reachable_floor),
+        ),
+    ];
+    for (name, value) in rows {
+        println!("{:<14}{}", format!("{}:", name), value);
+    }
+    Ok(())
+}
+
+fn check(args: &Args, indexes: &[String]) -> Result<()> {
+    let archive = args.archive()?;
+    let variant = args.variant()?;
+    let indexes = if indexes.is_empty() {
+        (0..archive.len()).collect()
+    } else {
+        indexes
+            .iter()
+            .map(|x| parse_index(x))
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    let mut errors = 0;
+    for index in indexes {
+        let map = archive.map(index)?;
+        for finding in validate(&map, variant) {
+            if finding.problem.severity() == Severity::Error {
+                errors += 1;
+            }
+            println!("map {} ({}): {}", index, map.name, finding);
+        }
+    }
+
+    if errors > 0 {
+        return Err(format!("{} errors found", errors).into());
+    }
+    Ok(())
+}
+
+fn convert(args: &Args, sources: &[String], output: &str) -> Result<()> {
+    let maps = sources
+        .iter()
+        .map(|source| args.load(source))
+        .collect::<Result<Vec<_>>>()?;
+
+    let format = extension(output);
+    let single = || match &maps[..] {
+        [map] => Ok(map),
+        _ => usage(format!("{} holds a single map", output)),
+    };
+
+    match format.as_deref() {
+        Some("tmj" | "json" | "tmx") => write_tiled(single()?, args.variant()?, output),
+        Some("ppm" | "png") => write_image(&Image::render(single()?, args.variant()?, 8), output),
+        _ => {
+            fs::create_dir_all(output)?;
+            Ok(wolf3d::write_gamemaps(output, args.variant()?, &maps)?)
+        }
+    }
+}
+
+fn diff(args: &Args, old: &str, new: &str) -> Result<()> {
+    let (old, new) = (args.load(old)?, args.load(new)?);
+    let diff = MapDiff::new(&old, &new, args.variant()?);
+    print!("{}", diff);
+
+    if let Some(path) = &args.overlay {
+        write_image(&diff.overlay(&new, 8), path)?;
+    }
+    Ok(())
+}
+
+fn write_image(image: &Image, path: &str) -> Result<()> {
+    match extension(path).as_deref() {
+        Some("ppm") => {
+            let mut file = BufWriter::new(File::create(path)?);
+            image.write_ppm(&mut file)?;
+            file.flush()?;
+            Ok(())
+        }
+        Some("png") => write_png(image, path),
+        _ => usage(format!("{} is not a .ppm or .png file", path)),
+    }
+}
+
+fn extension(path: &str) -> Option<String> {
+    Path::new(path)
+        .extension()
+        .map(|x| x.to_string_lossy().to_ascii_lowercase())
+}
+
+#[cfg(feature = "tiled")]
+fn read_tiled(path: &str) -> Result<MapData> {
+    use wolf3d::tiled::TiledMap;
+
+    let text = fs::read_to_string(path)?;
+    let tiled = if extension(path).as_deref() == Some("tmx") {
+        TiledMap::from_tmx(&text)?
+    } else {
+        TiledMap::from_json(&text)?
+    };
+    Ok(tiled.to_map()?)
+}
+
+#[cfg(not(feature = "tiled"))]
+fn read_tiled(path: &str) -> Result<MapData> {
+    Err(
+        wolf3d::Wolf3dError::Unsupported(format!("reading {} without the tiled feature", path))
+            .into(),
+    )
+}
+
+#[cfg(feature = "tiled")]
+fn write_tiled(map: &MapData, variant: Variant, path: &str) -> Result<()> {
+    let tiled = wolf3d::tiled::TiledMap::from_map(map, variant);
+    let mut file = BufWriter::new(File::create(path)?);
+    if extension(path).as_deref() == Some("tmx") {
+        tiled.write_tmx(&mut file)?;
+    } else {
+        tiled.write_json(&mut file)?;
+    }
+    file.flush()?;
+    Ok(())
+}
+
+#[cfg(not(feature = "tiled"))]
+fn write_tiled(_: &MapData, _: Variant, path: &str) -> Result<()> {
+    Err(
+        wolf3d::Wolf3dError::Unsupported(format!("writing {} without the tiled feature", path))
+            .into(),
+    )
+}
+
+#[cfg(feature = "png")]
+fn write_png(image: &Image, path: &str) -> Result<()> {
+    Ok(image.write_png(BufWriter::new(File::create(path)?))?)
+}
+
+#[cfg(not(feature = "png"))]
+fn write_png(_: &Image, path: &str) -> Result<()> {
+    Err(
+        wolf3d::Wolf3dError::Unsupported(format!("writing {} without the png feature", path))
+            .into(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args> {
+        Args::parse(args.iter().map(|x| x.to_string()))
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let args = parse(&[
+            "dump", "--data", "maps.zip", "3", "--format", "raw", "--plane", "1",
+        ])
+        .unwrap();
+
+        assert_eq!(args.data, "maps.zip");
+        assert_eq!(args.format.as_deref(), Some("raw"));
+        assert_eq!(args.plane, Some(1));
+        assert_eq!(args.map_format, None);
+        assert_eq!(args.positional, vec!["dump", "3"]);
+    }
+
+    #[test]
+    fn test_usage_errors() {
+        for args in [
+            &["--data"][..],
+            &["--variant", "XYZ", "list"],
+            &["--plane", "3", "dump", "0"],
+            &["--verbose", "list"],
+            &["--map-format", "lzw", "list"],
+            &["dump", "0", "--plane", "1", "--format", "text"],
+            &["dump", "0", "--plane", "1"],
+        ] {
+            assert!(parse(args).unwrap_err().is::<Usage>(), "{:?}", args);
+        }
+        assert!(run(vec!["frob".to_string()]).unwrap_err().is::<Usage>());
+        assert!(run(vec!["dump".to_string()]).unwrap_err().is::<Usage>());
+        assert!(parse_index("x").unwrap_err().is::<Usage>());
+    }
+}
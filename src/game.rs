@@ -1,18 +1,18 @@
-use std::io;
-
 use level::Level;
 use player::Player;
 
 mod enemy;
 mod level;
 mod player;
-use crate::wolf3d::MapData;
-
-const EMPTY_TILE: u16 = 106;
+use crate::wolf3d::stats::ratio;
+pub use crate::wolf3d::tiles::Difficulty;
+use crate::wolf3d::tiles::{AmbushTile, DoorLock, DoorTile, MapObject, Tile};
+use crate::wolf3d::validate::{Severity, validate};
+use crate::wolf3d::{MapArchive, MapData, Variant, Wolf3dError};
+
+// Opened doors and pushwalls leave plain floor behind.
+const EMPTY_TILE: Tile = Tile::Ambush(AmbushTile);
 const EMPTY_ITEM: u16 = 0;
-const SECRET_PUSH_WALL: u16 = 98;
-const DOOR_VERTICAL: u16 = 90;
-const DOOR_HORIZONTAL: u16 = 91;
 
 const ANSI_RESET: &str = "\x1B[0m";
 
@@ -29,17 +29,12 @@ const ANSI_GRAY_BG: &str = "\x1B[47m";
 pub struct Game {
     player: Player,
     level: Level,
-    maps: Vec<MapData>,
+    maps: MapArchive,
+    variant: Variant,
     message: String,
     difficulty: Difficulty,
 }
 
-pub enum Difficulty {
-    Easy,
-    Medium,
-    Hard,
-}
-
 pub enum Direction {
     Left,
     Right,
@@ -48,21 +43,23 @@ pub enum Direction {
 }
 
 impl Game {
-    pub fn new(maps: Vec<MapData>) -> Self {
-        let map = &maps[0];
-        let mut level = Level::new(0, map);
+    pub fn new(maps: MapArchive, variant: Variant) -> Result<Self, Wolf3dError> {
+        let difficulty = Difficulty::Medium;
+        let map = maps.map(0)?;
+        let mut level = Level::new(0, &map, variant, difficulty);
 
         let (x, y) = level.start;
 
         level.update_visibility(x, y);
 
-        Self {
+        Ok(Self {
             player: Player::new(x, y),
             level,
             maps,
-            message: String::new(),
-            difficulty: Difficulty::Medium,
-        }
+            variant,
+            message: map_errors(&map, variant).unwrap_or_default(),
+            difficulty,
+        })
     }
 
     pub fn get_message(&self) -> &str {
@@ -75,8 +72,8 @@ impl Game {
 
     pub fn move_player(&mut self, direction: &Direction) {
         self.message = String::new();
-        let (x, y) = self.player.get_next_position(direction);
-        if self.level.check_walkable(x, y) {
+        let next = self.player.get_next_position(direction);
+        if let Some((x, y)) = next.filter(|&(x, y)| self.level.check_walkable(x, y)) {
             self.player.move_player(direction);
             self.level.update_visibility(x, y);
             if self.level.check_exit(x, y) {
@@ -89,192 +86,121 @@ impl Game {
 
     pub fn open_door(&mut self) {
         let (x, y) = self.player.get_position();
-        let north = self.level.plane0[(y * self.level.width + x - self.level.width) as usize];
-        let south = self.level.plane0[(y * self.level.width + x + self.level.width) as usize];
-        let west = self.level.plane0[(y * self.level.width + x - 1) as usize];
-        let east = self.level.plane0[(y * self.level.width + x + 1) as usize];
-
-        if north == DOOR_VERTICAL || north == DOOR_HORIZONTAL {
-            self.level.set_tile(x, y - 1, EMPTY_TILE);
-        }
-
-        if south == DOOR_VERTICAL || south == DOOR_HORIZONTAL {
-            self.level.set_tile(x, y + 1, EMPTY_TILE);
-        }
-
-        if west == DOOR_VERTICAL || west == DOOR_HORIZONTAL {
-            self.level.set_tile(x - 1, y, EMPTY_TILE);
-        }
-
-        if east == DOOR_VERTICAL || east == DOOR_HORIZONTAL {
-            self.level.set_tile(x + 1, y, EMPTY_TILE);
+        let doors = self
+            .level
+            .plane0
+            .neighbors(x, y)
+            .filter(|&(_, _, tile)| is_door(tile))
+            .collect::<Vec<_>>();
+
+        for (x, y, _) in doors {
+            self.level.set_tile(x, y, EMPTY_TILE.to_u16());
+            self.level.areas.set_door_open(x, y, true);
         }
     }
 
     pub fn search_secret(&mut self) {
         let (x, y) = self.player.get_position();
-        let north = self.level.plane1[(y * self.level.width + x - self.level.width) as usize];
-        let south = self.level.plane1[(y * self.level.width + x + self.level.width) as usize];
-        let west = self.level.plane1[(y * self.level.width + x - 1) as usize];
-        let east = self.level.plane1[(y * self.level.width + x + 1) as usize];
-
-        if north == SECRET_PUSH_WALL {
-            self.level.set_tile(x, y - 1, EMPTY_TILE);
-            self.level.set_item(x, y - 1, EMPTY_ITEM);
-
-            self.message = "Secret push wall found".to_string();
-        }
-
-        if south == SECRET_PUSH_WALL {
-            self.level.set_tile(x, y + 1, EMPTY_TILE);
-            self.level.set_item(x, y + 1, EMPTY_ITEM);
-
-            self.message = "Secret push wall found".to_string();
-        }
-
-        if west == SECRET_PUSH_WALL {
-            self.level.set_tile(x - 1, y, EMPTY_TILE);
-            self.level.set_item(x - 1, y, EMPTY_ITEM);
+        let pushwalls = self
+            .level
+            .plane1
+            .neighbors(x, y)
+            .filter(|&(_, _, item)| self.is_pushwall(item))
+            .collect::<Vec<_>>();
+
+        for (x, y, _) in pushwalls {
+            self.level.set_tile(x, y, EMPTY_TILE.to_u16());
+            self.level.set_item(x, y, EMPTY_ITEM);
+            self.level.secrets_found += 1;
 
             self.message = "Secret push wall found".to_string();
         }
+    }
 
-        if east == SECRET_PUSH_WALL {
-            self.level.set_tile(x + 1, y, EMPTY_TILE);
-            self.level.set_item(x + 1, y, EMPTY_ITEM);
-
-            self.message = "Secret push wall found".to_string();
-        }
+    fn is_pushwall(&self, item: u16) -> bool {
+        MapObject::from_u16(item, self.variant) == Some(MapObject::Pushwall)
     }
 
     pub fn next_level(&mut self) {
-        self.level = Level::new(
-            self.level.number + 1,
-            &self.maps[(self.level.number + 1) as usize],
-        );
+        if self.level.number as usize + 1 >= self.maps.len() {
+            self.message = "No more levels in this data set".to_string();
+            return;
+        }
 
-        for i in 0..self.level.plane1.len() {
-            let p1 = match self.level.plane1[i] {
-                19..=22 => true,
-                _ => false,
-            };
-
-            if p1 == true {
-                let x = i as u16 % self.level.width;
-                let y = i as u16 / self.level.width;
-                self.player.set_position(x, y);
-                break;
+        let map = match self.maps.map((self.level.number + 1) as usize) {
+            Ok(map) => map,
+            Err(e) => {
+                self.message = format!("Can't load next level: {}", e);
+                return;
             }
+        };
+        // The ratios the intermission screen would show for the level just left.
+        self.message = format!(
+            "{} completed: secrets {}/{} ({}%)",
+            self.level.name,
+            self.level.secrets_found,
+            self.level.stats.secrets,
+            ratio(self.level.secrets_found, self.level.stats.secrets)
+        );
+        if let Some(errors) = map_errors(&map, self.variant) {
+            self.message = format!("{}; {}", self.message, errors);
         }
+        self.level = Level::new(self.level.number + 1, &map, self.variant, self.difficulty);
 
-        let (x, y) = self.player.get_position();
+        let (x, y) = self.level.start;
+        self.player.set_position(x, y);
         self.level.update_visibility(x, y);
     }
 
     pub fn print_map(&self) {
-        enum P1TileType {
-            None,
-            Loot,
-            Enemy,
-            Misc,
-        }
-        fn get_p1_value(value: u16) -> P1TileType {
-            match value {
-                19..=22 => P1TileType::None,
-                52..=55 => P1TileType::Loot,
-                108..=111 => P1TileType::Enemy, // standing guard, easy
-                112..=115 => P1TileType::Enemy, // patroling guard, easy
-                116..=119 => P1TileType::Enemy, // standing officer, easy
-                120..=123 => P1TileType::Enemy, // patroling officer, easy
-                124 => P1TileType::Misc,        // dead guard
-                126..=129 => P1TileType::Enemy, // standing ss, easy
-                130..=133 => P1TileType::Enemy, // patroling ss, easy
-                134..=137 => P1TileType::Enemy, // standing dog, easy
-                138..=141 => P1TileType::Enemy, // patroling dog, easy
-                144..=147 => P1TileType::Enemy, // standing guard, medium
-                148..=151 => P1TileType::Enemy, // patroling guard, medium
-                152..=155 => P1TileType::Enemy, // standing officer, medium
-                156..=159 => P1TileType::Enemy, // patroling officer, medium
-                160 => P1TileType::Enemy,       // Fake Hitler
-                162..=165 => P1TileType::Enemy, // standing ss, medium
-                166..=169 => P1TileType::Enemy, // patroling ss, medium
-                170..=173 => P1TileType::Enemy, // standing dog, medium
-                174..=177 => P1TileType::Enemy, // patroling dog, medium
-                178 => P1TileType::Enemy,       // Hitler
-                179 => P1TileType::Enemy,       // Fat
-                180..=183 => P1TileType::Enemy, // standing guard, hard
-                184..=187 => P1TileType::Enemy, // patroling guard, hard
-                188..=191 => P1TileType::Enemy, // standing officer, hard
-                192..=195 => P1TileType::Enemy, // patroling officer, hard
-                196 => P1TileType::Enemy,       // Schabbs
-                197 => P1TileType::Enemy,       // Gretel
-                198..=201 => P1TileType::Enemy, // standing ss, hard
-                202..=205 => P1TileType::Enemy, // patroling ss, hard
-                206..=209 => P1TileType::Enemy, // standing dog, hard
-                210..=213 => P1TileType::Enemy, // patroling dog, hard
-                214 => P1TileType::Enemy,       // Boss
-                215 => P1TileType::Enemy,       // Gift
-                216..=219 => P1TileType::Enemy, // standing mutant, easy
-                220..=223 => P1TileType::Enemy, // patroling mutant, easy
-                224 => P1TileType::Enemy,       // Blinky
-                225 => P1TileType::Enemy,       // Clyde
-                226 => P1TileType::Enemy,       // Pinky
-                227 => P1TileType::Enemy,       // Inky
-                234..=237 => P1TileType::Enemy, // standing mutant, medium
-                238..=241 => P1TileType::Enemy, // patroling mutant, medium
-                252..=255 => P1TileType::Enemy, // standing mutant, hard
-                256..=259 => P1TileType::Enemy, // patroling mutant, hard
-
-                _ => P1TileType::None,
-            }
-        }
-
         fn print_ch(ch: char, color: &str) {
             print!("{}{}{}", color, ch, ANSI_RESET);
         }
 
-        println!("Level: {}", self.level.name);
-        for y in 0..self.level.height {
-            for x in 0..self.level.width {
-                let idx = y * self.level.width + x;
-                if self.level.visible[idx as usize] == 0 {
+        println!("{} - Level: {}", self.variant.name(), self.level.name);
+        for y in 0..self.level.height() {
+            for x in 0..self.level.width() {
+                if !self.level.is_visible(x, y) {
                     print!(" ");
                     continue;
                 }
                 let (p_x, p_y) = self.player.get_position();
-                if x == p_x as u16 && y == p_y as u16 {
+                if x == p_x && y == p_y {
                     print_ch('@', ANSI_BLUE);
                     continue;
                 }
 
-                let p1 = self.level.plane1[idx as usize];
-                if p1 != 0 {
-                    let val = get_p1_value(p1);
-                    match val {
-                        P1TileType::Loot => {
-                            print_ch('$', ANSI_YELLOW);
-                            continue;
-                        }
-                        P1TileType::Enemy => {
-                            print_ch('E', ANSI_RED);
-                            continue;
-                        }
-                        P1TileType::Misc => {
-                            print_ch('M', ANSI_GREEN);
-                            continue;
-                        }
-                        _ => (),
+                let p1 = self.level.plane1.get(x, y).unwrap_or(EMPTY_ITEM);
+                match MapObject::from_u16(p1, self.variant) {
+                    Some(MapObject::Static(object)) if object.is_treasure() => {
+                        print_ch('$', ANSI_YELLOW);
+                        continue;
+                    }
+                    Some(MapObject::Enemy(_) | MapObject::Boss(_)) => {
+                        print_ch('E', ANSI_RED);
+                        continue;
+                    }
+                    Some(MapObject::DeadGuard) => {
+                        print_ch('M', ANSI_GREEN);
+                        continue;
                     }
+                    _ => (),
                 }
 
-                let p0 = self.level.plane0[idx as usize];
+                let p0 = self.level.plane0.get(x, y).and_then(Tile::from_u16);
                 let ch = match p0 {
-                    0..=63 => 'W',
-                    90..=91 => '#',
-                    92..=95 => 'X',
-                    100..=101 => 'v',
-                    106..=143 => '.',
-                    _ => ' ',
+                    Some(Tile::Empty | Tile::Wall(_) | Tile::Elevator(_) | Tile::Solid(_)) => 'W',
+                    Some(Tile::Door(DoorTile {
+                        lock: DoorLock::Normal,
+                        ..
+                    })) => '#',
+                    Some(Tile::Door(DoorTile {
+                        lock: DoorLock::Elevator,
+                        ..
+                    })) => 'v',
+                    Some(Tile::Door(_)) => 'X',
+                    Some(Tile::Ambush(_) | Tile::Area(_)) => '.',
+                    None => ' ',
                 };
 
                 if ch == 'W' {
@@ -293,7 +219,37 @@ impl Game {
 
                 print!("{}", ch);
             }
-            print!("\n");
+            println!();
         }
     }
 }
+
+// Broken custom maps still load, but say why they may not play.
+fn map_errors(map: &MapData, variant: Variant) -> Option<String> {
+    let errors: Vec<String> = validate(map, variant)
+        .into_iter()
+        .filter(|x| x.problem.severity() == Severity::Error)
+        .map(|x| x.to_string())
+        .collect();
+
+    match errors.len() {
+        0 => None,
+        1..=3 => Some(format!("{}: {}", map.name, errors.join("; "))),
+        n => Some(format!(
+            "{}: {} and {} more",
+            map.name,
+            errors[..3].join("; "),
+            n - 3
+        )),
+    }
+}
+
+fn is_door(tile: u16) -> bool {
+    matches!(
+        Tile::from_u16(tile),
+        Some(Tile::Door(DoorTile {
+            lock: DoorLock::Normal,
+            ..
+        }))
+    )
+}
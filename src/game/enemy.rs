@@ -1,14 +1,16 @@
+#[allow(dead_code)]
 pub struct Enemy {
-    x: u16,
-    y: u16,
+    x: usize,
+    y: usize,
     patrol: bool,
     active: bool,
     health: u8,
     damage: u8,
 }
 
+#[allow(dead_code)]
 impl Enemy {
-    fn new(x: u16, y: u16, patrol: bool, health: u8, damage: u8) -> Self {
+    fn new(x: usize, y: usize, patrol: bool, health: u8, damage: u8) -> Self {
         Self {
             x,
             y,
@@ -19,23 +21,23 @@ impl Enemy {
         }
     }
 
-    pub fn spawn_dog(x: u16, y: u16, patrol: bool) -> Self {
+    pub fn spawn_dog(x: usize, y: usize, patrol: bool) -> Self {
         Self::new(x, y, patrol, 5, 1)
     }
 
-    pub fn spawn_guard(x: u16, y: u16, patrol: bool) -> Self {
+    pub fn spawn_guard(x: usize, y: usize, patrol: bool) -> Self {
         Self::new(x, y, patrol, 10, 1)
     }
 
-    pub fn spawn_officer(x: u16, y: u16, patrol: bool) -> Self {
+    pub fn spawn_officer(x: usize, y: usize, patrol: bool) -> Self {
         Self::new(x, y, patrol, 15, 2)
     }
 
-    pub fn spawn_ss(x: u16, y: u16, patrol: bool) -> Self {
+    pub fn spawn_ss(x: usize, y: usize, patrol: bool) -> Self {
         Self::new(x, y, patrol, 20, 2)
     }
 
-    pub fn spawn_mutant(x: u16, y: u16, patrol: bool) -> Self {
+    pub fn spawn_mutant(x: usize, y: usize, patrol: bool) -> Self {
         Self::new(x, y, patrol, 10, 2)
     }
 }
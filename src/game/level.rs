@@ -1,151 +1,137 @@
-use byteorder::{LittleEndian, ReadBytesExt};
-use std::io::BufReader;
-
-use crate::wolf3d::MapData;
+use crate::wolf3d::areas::Areas;
+use crate::wolf3d::stats::MapStats;
+use crate::wolf3d::tiles::{Difficulty, DoorLock, DoorTile, Tile};
+use crate::wolf3d::{Grid, MapData, Plane, Variant};
 
 use super::enemy::Enemy;
 
-const EXIT_TILE: u16 = 100;
-const EXIT_TILE2: u16 = 101;
-
 pub struct Level {
     pub name: String,
     pub number: u16,
-    pub height: u16,
-    pub width: u16,
-    pub plane0: Vec<u16>,
-    pub plane1: Vec<u16>,
-    pub visible: Vec<u16>,
+    pub plane0: Plane,
+    pub plane1: Plane,
+    pub visible: Grid<bool>,
+    pub areas: Areas,
+    #[allow(dead_code)]
     pub enemies: Vec<Enemy>,
-    pub start: (u16, u16),
+    pub start: (usize, usize),
+    pub stats: MapStats,
+    pub secrets_found: usize,
 }
 
 impl Level {
-    pub fn new(number: u16, map: &MapData) -> Self {
-        let mut plane0buf = BufReader::new(&map.plane0[..]);
-        let mut plane1buf = BufReader::new(&map.plane1[..]);
-        let mut plane0 = vec![];
-        let mut plane1 = vec![];
-        let mut start = (0, 0);
-        for y in 0..map.height {
-            for x in 0..map.width {
-                plane0.push(plane0buf.read_u16::<LittleEndian>().unwrap());
-                let p1_tile = plane1buf.read_u16::<LittleEndian>().unwrap();
-                plane1.push(p1_tile);
-                match p1_tile {
-                    19..=22 => {
-                        start = (x, y);
-                    }
-                    // 52..=55 => P1TileType::Loot,
-                    // 108..=111 => P1TileType::Enemy, // standing guard, easy
-                    // 112..=115 => P1TileType::Enemy, // patroling guard, easy
-                    // 116..=119 => P1TileType::Enemy, // standing officer, easy
-                    // 120..=123 => P1TileType::Enemy, // patroling officer, easy
-                    // 124 => P1TileType::Misc,        // dead guard
-                    // 126..=129 => P1TileType::Enemy, // standing ss, easy
-                    // 130..=133 => P1TileType::Enemy, // patroling ss, easy
-                    // 134..=137 => P1TileType::Enemy, // standing dog, easy
-                    // 138..=141 => P1TileType::Enemy, // patroling dog, easy
-                    // 144..=147 => P1TileType::Enemy, // standing guard, medium
-                    // 148..=151 => P1TileType::Enemy, // patroling guard, medium
-                    // 152..=155 => P1TileType::Enemy, // standing officer, medium
-                    // 156..=159 => P1TileType::Enemy, // patroling officer, medium
-                    // 160 => P1TileType::Enemy,       // Fake Hitler
-                    // 162..=165 => P1TileType::Enemy, // standing ss, medium
-                    // 166..=169 => P1TileType::Enemy, // patroling ss, medium
-                    // 170..=173 => P1TileType::Enemy, // standing dog, medium
-                    // 174..=177 => P1TileType::Enemy, // patroling dog, medium
-                    // 178 => P1TileType::Enemy,       // Hitler
-                    // 179 => P1TileType::Enemy,       // Fat
-                    // 180..=183 => P1TileType::Enemy, // standing guard, hard
-                    // 184..=187 => P1TileType::Enemy, // patroling guard, hard
-                    // 188..=191 => P1TileType::Enemy, // standing officer, hard
-                    // 192..=195 => P1TileType::Enemy, // patroling officer, hard
-                    // 196 => P1TileType::Enemy,       // Schabbs
-                    // 197 => P1TileType::Enemy,       // Gretel
-                    // 198..=201 => P1TileType::Enemy, // standing ss, hard
-                    // 202..=205 => P1TileType::Enemy, // patroling ss, hard
-                    // 206..=209 => P1TileType::Enemy, // standing dog, hard
-                    // 210..=213 => P1TileType::Enemy, // patroling dog, hard
-                    // 214 => P1TileType::Enemy,       // Boss
-                    // 215 => P1TileType::Enemy,       // Gift
-                    // 216..=219 => P1TileType::Enemy, // standing mutant, easy
-                    // 220..=223 => P1TileType::Enemy, // patroling mutant, easy
-                    // 224 => P1TileType::Enemy,       // Blinky
-                    // 225 => P1TileType::Enemy,       // Clyde
-                    // 226 => P1TileType::Enemy,       // Pinky
-                    // 227 => P1TileType::Enemy,       // Inky
-                    // 234..=237 => P1TileType::Enemy, // standing mutant, medium
-                    // 238..=241 => P1TileType::Enemy, // patroling mutant, medium
-                    // 252..=255 => P1TileType::Enemy, // standing mutant, hard
-                    // 256..=259 => P1TileType::Enemy, // patroling mutant, hard
-                    _ => (),
-                }
-            }
-        }
+    pub fn new(number: u16, map: &MapData, variant: Variant, difficulty: Difficulty) -> Self {
+        let start = map.player_start(variant).unwrap_or((0, 0));
 
         Self {
             name: map.name.clone(),
             number,
-            height: map.height,
-            width: map.width,
-            plane0,
-            plane1,
-            visible: vec![0; (map.width * map.height) as usize],
+            plane0: map.plane0.clone(),
+            plane1: map.plane1.clone(),
+            visible: Grid::new(map.plane0.width(), map.plane0.height(), false),
+            areas: Areas::new(&map.plane0),
             enemies: vec![],
             start,
+            stats: MapStats::new(map, variant, difficulty),
+            secrets_found: 0,
         }
     }
 
-    pub fn update_visibility(&mut self, x: u16, y: u16) {
-        // self.visible = vec![0; (self.width * self.height) as usize];
-        let visibility_radius = 5;
-        let visibility_radius_squared = (visibility_radius * visibility_radius) as i16;
+    pub fn width(&self) -> usize {
+        self.plane0.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.plane0.height()
+    }
 
-        for i in 0..self.visible.len() {
-            let x2 = i as u16 % self.width;
-            let y2 = i as u16 / self.width;
-            let dx = x as i16 - x2 as i16;
-            let dy = y as i16 - y2 as i16;
-            let distance_squared = dx * dx + dy * dy;
+    // Reveals a circle around the position, clipped to the map.
+    pub fn update_visibility(&mut self, x: usize, y: usize) {
+        let visibility_radius = 5;
+        if self.width() == 0 || self.height() == 0 {
+            return;
+        }
 
-            if distance_squared <= visibility_radius_squared {
-                self.visible[i] = 1;
+        let right = x.saturating_add(visibility_radius).min(self.width() - 1);
+        let bottom = y.saturating_add(visibility_radius).min(self.height() - 1);
+        for y2 in y.saturating_sub(visibility_radius)..=bottom {
+            for x2 in x.saturating_sub(visibility_radius)..=right {
+                if x.abs_diff(x2).pow(2) + y.abs_diff(y2).pow(2) <= visibility_radius.pow(2) {
+                    self.visible.set(x2, y2, true);
+                }
             }
         }
     }
 
-    pub fn check_walkable(&self, x: u16, y: u16) -> bool {
-        fn check_tile(tile: u16) -> bool {
-            match tile {
-                0..=63 => false,  // wall
-                90..=91 => false, // door
-                92..=95 => false, // locked door
-                100..=101 => true,
-                106..=143 => true,
-                _ => false,
-            }
+    pub fn is_visible(&self, x: usize, y: usize) -> bool {
+        self.visible.get(x, y).unwrap_or(false)
+    }
+
+    pub fn check_walkable(&self, x: usize, y: usize) -> bool {
+        match self.plane0.get(x, y).and_then(Tile::from_u16) {
+            Some(tile) => tile.is_floor() || is_exit(tile),
+            None => false,
         }
-        let index = (y * self.width + x) as usize;
-        check_tile(self.plane0[index])
     }
 
     pub fn show_all(&mut self) {
-        self.visible = vec![1; (self.width * self.height) as usize];
+        self.visible.fill(true);
     }
 
-    pub fn check_exit(&mut self, x: u16, y: u16) -> bool {
-        let tile = self.plane0[(y * self.width + x) as usize];
-        tile == EXIT_TILE || tile == EXIT_TILE2
+    pub fn check_exit(&mut self, x: usize, y: usize) -> bool {
+        self.plane0
+            .get(x, y)
+            .and_then(Tile::from_u16)
+            .is_some_and(is_exit)
     }
 
-    pub fn set_tile(&mut self, x: u16, y: u16, tile: u16) {
-        let index = (y * self.width + x) as usize;
-        self.plane0[index] = tile;
+    pub fn set_tile(&mut self, x: usize, y: usize, tile: u16) {
+        self.plane0.set(x, y, tile);
     }
 
-    pub fn set_item(&mut self, x: u16, y: u16, item: u16) {
-        let index = (y * self.width + x) as usize;
-        self.plane1[index] = item;
+    pub fn set_item(&mut self, x: usize, y: usize, item: u16) {
+        self.plane1.set(x, y, item);
+    }
+}
+
+// Walking through an elevator door ends the level.
+fn is_exit(tile: Tile) -> bool {
+    matches!(
+        tile,
+        Tile::Door(DoorTile {
+            lock: DoorLock::Elevator,
+            ..
+        })
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_large_non_square_level() {
+        let mut map = MapData::test_map("Hangar", 200, 90, 0);
+        map.plane1.fill(0);
+        map.plane1.set(198, 88, 19);
+
+        let mut level = Level::new(0, &map, Variant::Wolf3d, Difficulty::Hard);
+        assert_eq!((level.width(), level.height()), (200, 90));
+        assert_eq!(level.start, (198, 88));
+        assert!(level.check_walkable(198, 88));
+        assert!(!level.check_walkable(199, 89));
+        assert!(!level.check_walkable(200, 10));
+        assert!(!level.check_walkable(10, 90));
+
+        level.update_visibility(198, 88);
+        level.update_visibility(0, 0);
+        level.update_visibility(usize::MAX, usize::MAX);
+
+        assert!(level.is_visible(199, 89));
+        assert!(level.is_visible(193, 88));
+        assert!(!level.is_visible(192, 88));
+        assert!(level.is_visible(0, 5));
+        assert!(!level.is_visible(100, 45));
+        assert!(!level.is_visible(200, 89));
     }
 }
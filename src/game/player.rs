@@ -1,55 +1,61 @@
 use super::Direction;
 
 pub struct Player {
-    x: u16,
-    y: u16,
+    x: usize,
+    y: usize,
 }
 
 impl Player {
-    pub fn new(x: u16, y: u16) -> Self {
+    pub fn new(x: usize, y: usize) -> Self {
         Self { x, y }
     }
 
+    // Stays put when the move would leave the coordinate range.
     pub fn move_player(&mut self, dir: &Direction) {
-        match dir {
-            Direction::Up => self.move_north(),
-            Direction::Down => self.move_south(),
-            Direction::Left => self.move_west(),
-            Direction::Right => self.move_east(),
+        if let Some((x, y)) = self.get_next_position(dir) {
+            self.set_position(x, y);
         }
     }
 
-    fn move_north(&mut self) {
-        self.y -= 1;
-    }
-
-    fn move_south(&mut self) {
-        self.y += 1;
-    }
-
-    fn move_west(&mut self) {
-        self.x -= 1;
-    }
-
-    fn move_east(&mut self) {
-        self.x += 1;
-    }
-
-    pub fn get_position(&self) -> (u16, u16) {
+    pub fn get_position(&self) -> (usize, usize) {
         (self.x, self.y)
     }
 
-    pub fn set_position(&mut self, x: u16, y: u16) {
+    pub fn set_position(&mut self, x: usize, y: usize) {
         self.x = x;
         self.y = y;
     }
 
-    pub fn get_next_position(&self, dir: &Direction) -> (u16, u16) {
+    pub fn get_next_position(&self, dir: &Direction) -> Option<(usize, usize)> {
         match dir {
-            Direction::Up => (self.x, self.y - 1),
-            Direction::Down => (self.x, self.y + 1),
-            Direction::Left => (self.x - 1, self.y),
-            Direction::Right => (self.x + 1, self.y),
+            Direction::Up => Some((self.x, self.y.checked_sub(1)?)),
+            Direction::Down => Some((self.x, self.y.checked_add(1)?)),
+            Direction::Left => Some((self.x.checked_sub(1)?, self.y)),
+            Direction::Right => Some((self.x.checked_add(1)?, self.y)),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_move_at_the_edges() {
+        let mut player = Player::new(0, usize::MAX);
+
+        assert_eq!(player.get_next_position(&Direction::Left), None);
+        assert_eq!(player.get_next_position(&Direction::Down), None);
+        assert_eq!(
+            player.get_next_position(&Direction::Up),
+            Some((0, usize::MAX - 1))
+        );
+
+        player.move_player(&Direction::Left);
+        player.move_player(&Direction::Down);
+        assert_eq!(player.get_position(), (0, usize::MAX));
+
+        player.move_player(&Direction::Right);
+        assert_eq!(player.get_position(), (1, usize::MAX));
+    }
+}
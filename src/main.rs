@@ -2,16 +2,18 @@ use crossterm::{
     event::{self, Event, KeyCode},
     terminal::{disable_raw_mode, enable_raw_mode},
 };
-use game::Direction;
-use game::Game;
-
-mod game;
-mod wolf3d;
+use rougestein::game::Direction;
+use rougestein::game::Game;
+use rougestein::wolf3d::{MapArchive, Variant};
 
 fn main() {
-    let maps = wolf3d::read_gamemaps("assets").unwrap();
-
-    let mut g = Game::new(maps);
+    let mut g = match load_game("assets") {
+        Ok(game) => game,
+        Err(e) => {
+            eprintln!("Failed to load maps: {}", e);
+            std::process::exit(1);
+        }
+    };
 
     loop {
         print!("\x1B[1;1H");
@@ -28,23 +30,45 @@ fn main() {
 
         println!("Command [h/j/k/l/q]: ");
         enable_raw_mode().unwrap();
-        if event::poll(std::time::Duration::from_millis(1000)).unwrap() {
-            if let Event::Key(key_event) = event::read().unwrap() {
-                match key_event.code {
-                    KeyCode::Char('q') => break,
-                    KeyCode::Char('h') => g.move_player(&Direction::Left),
-                    KeyCode::Char('l') => g.move_player(&Direction::Right),
-                    KeyCode::Char('j') => g.move_player(&Direction::Down),
-                    KeyCode::Char('k') => g.move_player(&Direction::Up),
-                    KeyCode::Char('o') => g.open_door(),
-                    KeyCode::Char('s') => g.search_secret(),
-                    KeyCode::Char('w') => g.next_level(),
-                    KeyCode::Char('a') => g.reveal(),
-                    _ => (),
-                }
+        if event::poll(std::time::Duration::from_millis(1000)).unwrap()
+            && let Event::Key(key_event) = event::read().unwrap()
+        {
+            match key_event.code {
+                KeyCode::Char('q') => break,
+                KeyCode::Char('h') => g.move_player(&Direction::Left),
+                KeyCode::Char('l') => g.move_player(&Direction::Right),
+                KeyCode::Char('j') => g.move_player(&Direction::Down),
+                KeyCode::Char('k') => g.move_player(&Direction::Up),
+                KeyCode::Char('o') => g.open_door(),
+                KeyCode::Char('s') => g.search_secret(),
+                KeyCode::Char('w') => g.next_level(),
+                KeyCode::Char('a') => g.reveal(),
+                _ => (),
             }
         }
         disable_raw_mode().unwrap();
     }
     disable_raw_mode().unwrap();
 }
+
+fn load_game(path: &str) -> Result<Game, Box<dyn std::error::Error>> {
+    let mut path = path.to_string();
+    let mut variant = None;
+    for arg in std::env::args().skip(1) {
+        if arg.to_ascii_lowercase().ends_with(".zip") {
+            path = arg;
+        } else {
+            variant = Some(
+                Variant::from_extension(&arg)
+                    .ok_or(format!("unknown data file extension: {}", arg))?,
+            );
+        }
+    }
+    let variant = match variant {
+        Some(variant) => variant,
+        None => Variant::detect(&path)?,
+    };
+
+    let maps = MapArchive::open(&path, variant)?;
+    Ok(Game::new(maps, variant)?)
+}
@@ -1,88 +1,239 @@
-use std::{fs::File, io::Read};
+use std::{fs::File, io::Write, path::Path};
 
+pub use archive::{MapArchive, MapFormat, MapInfo};
+pub use audio::Audio;
+pub use error::Wolf3dError;
+pub use grid::{Grid, Plane};
 pub use mapdata::MapData;
-
-mod compression;
+pub use variant::Variant;
+pub use vgagraph::Vgagraph;
+pub use vswap::Vswap;
+
+mod archive;
+pub mod areas;
+pub mod audio;
+pub mod compression;
+pub mod diff;
+mod error;
 mod gamemaps;
+mod grid;
+pub mod image;
 mod mapdata;
 mod maphead;
+mod source;
+pub mod stats;
+#[cfg(feature = "tiled")]
+pub mod tiled;
+pub mod tiles;
+pub mod validate;
+mod variant;
+pub mod vgagraph;
+pub mod vswap;
+
+pub fn read_gamemaps(path: &str, variant: Variant) -> Result<Vec<MapData>, Wolf3dError> {
+    MapArchive::open(path, variant)?.maps()
+}
+
+pub fn read_gamemaps_as(
+    path: &str,
+    variant: Variant,
+    format: MapFormat,
+) -> Result<Vec<MapData>, Wolf3dError> {
+    let mut archive = MapArchive::open(path, variant)?;
+    archive.set_format(format);
+    archive.maps()
+}
+
+#[cfg(feature = "parallel")]
+pub fn read_gamemaps_parallel(path: &str, variant: Variant) -> Result<Vec<MapData>, Wolf3dError> {
+    MapArchive::open(path, variant)?.par_maps()
+}
+
+pub fn read_vswap(path: &str, variant: Variant) -> Result<Vswap, Wolf3dError> {
+    Vswap::parse(read_file(path, "VSWAP", variant)?)
+}
+
+pub fn read_vgagraph(path: &str, variant: Variant) -> Result<Vgagraph, Wolf3dError> {
+    Vgagraph::parse(
+        &read_file(path, "VGADICT", variant)?,
+        &read_file(path, "VGAHEAD", variant)?,
+        read_file(path, "VGAGRAPH", variant)?,
+    )
+}
+
+pub fn read_audio(path: &str, variant: Variant) -> Result<Audio, Wolf3dError> {
+    let layout = audio::AudioLayout::for_variant(variant).ok_or(Wolf3dError::Unsupported(
+        format!("audio for {}", variant.name()),
+    ))?;
+
+    Audio::parse(
+        &read_file(path, "AUDIOHED", variant)?,
+        read_file(path, "AUDIOT", variant)?,
+        layout,
+    )
+}
+
+const RLEW_TAG: u16 = 0xABCD;
+const SIGNATURE: &[u8] = b"TED5v1.0";
+
+pub fn write_gamemaps(path: &str, variant: Variant, maps: &[MapData]) -> Result<(), Wolf3dError> {
+    let mut maphead = vec![];
+    let mut gamemaps = vec![];
+    write_gamemaps_to(&mut maphead, &mut gamemaps, maps)?;
+
+    File::create(Path::new(path).join(variant.maphead_name()))?.write_all(&maphead)?;
+    File::create(Path::new(path).join(variant.gamemaps_name()))?.write_all(&gamemaps)?;
+
+    Ok(())
+}
 
-pub fn read_gamemaps(path: &str) -> Result<Vec<MapData>, Box<dyn std::error::Error>> {
-    let maphead_path = format!("{}/MAPHEAD.WL6", path);
-    let maphead_file = File::open(maphead_path);
+pub fn write_gamemaps_to(
+    maphead: &mut impl Write,
+    gamemaps: &mut impl Write,
+    maps: &[MapData],
+) -> Result<(), Wolf3dError> {
+    let mut ptr = [0; 100];
+    if maps.len() > ptr.len() {
+        return Err(Wolf3dError::TooManyMaps { count: maps.len() });
+    }
 
-    if maphead_file.is_err() {
-        return Err(Box::new(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "MAPHEAD.WL6 not found",
-        )));
+    let mut gamemaps_data = SIGNATURE.to_vec();
+    for (i, map) in maps.iter().enumerate() {
+        let mut offsets = [0; 3];
+        let mut lengths = [0; 3];
+        for (plane, data) in [&map.plane0, &map.plane1, &map.plane2]
+            .into_iter()
+            .enumerate()
+        {
+            if (data.width(), data.height()) != (map.width as usize, map.height as usize) {
+                return Err(Wolf3dError::SizeMismatch {
+                    map: i,
+                    plane,
+                    expected: map.width as usize * map.height as usize * 2,
+                    actual: data.cells().len() * 2,
+                });
+            }
+
+            let compressed = compression::rlew_compress(&data.to_bytes(), RLEW_TAG)
+                .and_then(|x| compression::carmack_compress(&x))
+                .map_err(|_| Wolf3dError::PlaneTooLarge { map: i, plane })?;
+            offsets[plane] = gamemaps_data.len() as i32;
+            lengths[plane] = u16::try_from(compressed.len())
+                .map_err(|_| Wolf3dError::PlaneTooLarge { map: i, plane })?;
+            gamemaps_data.extend_from_slice(&compressed);
+        }
+
+        ptr[i] = gamemaps_data.len() as i32;
+        gamemaps::Gamemaps::new(
+            offsets[0],
+            offsets[1],
+            offsets[2],
+            lengths[0],
+            lengths[1],
+            lengths[2],
+            map.width,
+            map.height,
+            map.name.clone(),
+        )
+        .write_to(&mut gamemaps_data)?;
     }
 
-    let maphead = maphead::Maphead::from_file(maphead_file.unwrap())?;
+    maphead::Maphead::new(RLEW_TAG, ptr).write_to(maphead)?;
+    gamemaps.write_all(&gamemaps_data)?;
+
+    Ok(())
+}
 
-    let gamemaps_path = format!("{}/GAMEMAPS.WL6", path);
-    let gamemaps_file = File::open(gamemaps_path);
+fn read_file(path: &str, name: &str, variant: Variant) -> Result<Vec<u8>, Wolf3dError> {
+    source::read_file(path, &format!("{}.{}", name, variant.extension()))
+}
 
-    if gamemaps_file.is_err() {
-        return Err(Box::new(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "GAMEMAPS.WL6 not found",
-        )));
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_gamemaps_round_trip() {
+        let dir = std::env::temp_dir().join(format!("roguestein-write-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.to_str().unwrap();
+        let maps = vec![
+            MapData::test_map("Wolf1 Map1", 64, 64, 0),
+            MapData::test_map("Wolf1 Map2", 64, 64, 1),
+            MapData::test_map("Wide", 80, 20, 2),
+        ];
+
+        write_gamemaps(path, Variant::Wolf3d, &maps).unwrap();
+        let gamemaps_data = std::fs::read(dir.join("GAMEMAPS.WL6")).unwrap();
+        let maphead_size = std::fs::metadata(dir.join("MAPHEAD.WL6")).unwrap().len();
+        let result = read_gamemaps(path, Variant::Wolf3d);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(&gamemaps_data[..8], SIGNATURE);
+        assert_eq!(maphead_size, 402);
+        assert_eq!(result.unwrap(), maps);
     }
 
-    let mut gamemaps_data = vec![];
-    let result = gamemaps_file.unwrap().read_to_end(&mut gamemaps_data);
+    #[test]
+    fn test_read_gamemaps_from_maptemp() {
+        let dir = std::env::temp_dir().join(format!("roguestein-maptemp-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.to_str().unwrap();
+        let maps = vec![MapData::test_map("Wolf1 Map1", 64, 64, 0)];
+        let mut maphead = vec![];
+        let mut gamemaps = vec![];
+        write_gamemaps_to(&mut maphead, &mut gamemaps, &maps).unwrap();
+        std::fs::write(dir.join("MAPTHEAD.SOD"), maphead).unwrap();
+        std::fs::write(dir.join("maptemp.sod"), gamemaps).unwrap();
+
+        let variant = Variant::detect(path);
+        let result = read_gamemaps(path, Variant::SpearOfDestiny);
+        let explicit = read_gamemaps_as(path, Variant::SpearOfDestiny, MapFormat::Raw);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(variant.unwrap(), Variant::SpearOfDestiny);
+        assert_eq!(result.unwrap(), maps);
+        assert!(matches!(
+            explicit,
+            Err(Wolf3dError::SizeMismatch { map: 0, .. })
+        ));
+    }
 
-    if result.is_err() {
-        return Err(Box::new(result.unwrap_err()));
+    // Plane sizes are stored as 16-bit byte counts, which caps a map at 32767 tiles.
+    #[test]
+    fn test_write_gamemaps_map_sizes() {
+        let fits = vec![MapData::test_map("Big", 128, 128, 0)];
+        let too_large = vec![MapData::test_map("Huge", 256, 256, 0)];
+        let mut maphead = vec![];
+        let mut gamemaps = vec![];
+
+        write_gamemaps_to(&mut maphead, &mut gamemaps, &fits).unwrap();
+        let archive = MapArchive::from_bytes(&maphead, &gamemaps).unwrap();
+
+        assert_eq!(archive.maps().unwrap(), fits);
+        assert!(matches!(
+            write_gamemaps_to(&mut vec![], &mut vec![], &too_large),
+            Err(Wolf3dError::PlaneTooLarge { map: 0, plane: 0 })
+        ));
     }
 
-    let gamemaps = maphead
-        .ptr
-        .iter()
-        .filter(|&x| *x > 0)
-        .map(|x| {
-            let position = *x as usize;
-            let data = gamemaps_data.get(position..position + 42).unwrap();
-            return gamemaps::Gamemaps::parse(data);
-        })
-        .collect::<Vec<gamemaps::Gamemaps>>();
-
-    let mapdata = gamemaps
-        .iter()
-        .map(|x| {
-            let plane0_position = x.off_plane0 as usize;
-            let plane1_position = x.off_plane1 as usize;
-            let plane2_position = x.off_plane2 as usize;
-
-            let plane0_data = gamemaps_data
-                .get(plane0_position..plane0_position + x.len_plane0 as usize)
-                .unwrap();
-            let plane0_data = compression::carmack_expand(&plane0_data);
-            let plane0_data = compression::rlew_expand(&plane0_data, maphead.magic);
-
-            let plane1_data = gamemaps_data
-                .get(plane1_position..plane1_position + x.len_plane1 as usize)
-                .unwrap();
-            let plane1_data = compression::carmack_expand(&plane1_data);
-            let plane1_data = compression::rlew_expand(&plane1_data, maphead.magic);
-
-            let plane2_data = gamemaps_data
-                .get(plane2_position..plane2_position + x.len_plane2 as usize)
-                .unwrap();
-            let plane2_data = compression::carmack_expand(&plane2_data);
-            let plane2_data = compression::rlew_expand(&plane2_data, maphead.magic);
-
-            return mapdata::MapData::new(
-                plane0_data,
-                plane1_data,
-                plane2_data,
-                x.width,
-                x.height,
-                x.name.clone(),
-            );
-        })
-        .collect::<Vec<mapdata::MapData>>();
-
-    Ok(mapdata)
+    #[test]
+    fn test_write_gamemaps_rejects_wrong_plane_size() {
+        let mut map = MapData::test_map("Broken", 64, 64, 0);
+        map.plane1 = Plane::new(5, 1, 0);
+
+        let result = write_gamemaps("", Variant::Wolf3d, &[map]);
+
+        assert!(matches!(
+            result,
+            Err(Wolf3dError::SizeMismatch {
+                map: 0,
+                plane: 1,
+                expected: 8192,
+                actual: 10
+            })
+        ));
+    }
 }
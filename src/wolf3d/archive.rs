@@ -0,0 +1,556 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use super::{
+    Plane, Variant, Wolf3dError,
+    compression::{self, PlaneDecoder},
+    gamemaps::Gamemaps,
+    mapdata::MapData,
+    maphead::Maphead,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapInfo {
+    pub slot: usize,
+    pub name: String,
+    pub width: u16,
+    pub height: u16,
+    pub plane_lengths: [u16; 3],
+}
+
+// How planes are stored. The games ship Carmack compressed maps, TED5 saves RLEW encoded ones to
+// MAPTEMP, and some editors write plain words.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapFormat {
+    Carmack,
+    Rlew,
+    Raw,
+}
+
+impl MapFormat {
+    pub const ALL: [Self; 3] = [Self::Carmack, Self::Rlew, Self::Raw];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|x| x.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Carmack => "carmack",
+            Self::Rlew => "rlew",
+            Self::Raw => "raw",
+        }
+    }
+}
+
+// Map headers are parsed up front; planes are only decompressed when a map is requested.
+#[derive(Debug)]
+pub struct MapArchive {
+    magic: u16,
+    format: MapFormat,
+    data: Vec<u8>,
+    headers: Vec<(usize, Gamemaps)>,
+    cache: Vec<Option<MapData>>,
+}
+
+impl MapArchive {
+    pub fn open(path: &str, variant: Variant) -> Result<Self, Wolf3dError> {
+        let (maphead, gamemaps) = variant
+            .map_files(path)
+            .unwrap_or((variant.maphead_name(), variant.gamemaps_name()));
+        let maphead = super::source::read_file(path, &maphead)?;
+        let gamemaps = super::source::read_file(path, &gamemaps)?;
+
+        Self::new(Maphead::from_reader(&maphead[..])?, gamemaps)
+    }
+
+    pub fn from_readers(
+        mut maphead: impl Read + Seek,
+        mut gamemaps: impl Read + Seek,
+    ) -> Result<Self, Wolf3dError> {
+        maphead.seek(SeekFrom::Start(0))?;
+        let maphead = Maphead::from_reader(maphead)?;
+
+        let mut gamemaps_data = vec![];
+        gamemaps.seek(SeekFrom::Start(0))?;
+        gamemaps.read_to_end(&mut gamemaps_data)?;
+
+        Self::new(maphead, gamemaps_data)
+    }
+
+    pub fn from_bytes(maphead: &[u8], gamemaps: &[u8]) -> Result<Self, Wolf3dError> {
+        Self::new(Maphead::from_reader(maphead)?, gamemaps.to_vec())
+    }
+
+    fn new(maphead: Maphead, data: Vec<u8>) -> Result<Self, Wolf3dError> {
+        let headers = maphead
+            .ptr
+            .iter()
+            .enumerate()
+            .filter(|&(_, x)| *x > 0)
+            .map(|(i, x)| {
+                let position = *x as usize;
+                let header = data
+                    .get(position..position + super::gamemaps::HEADER_SIZE)
+                    .ok_or(if position < data.len() {
+                        Wolf3dError::TruncatedHeader { map: i }
+                    } else {
+                        Wolf3dError::BadOffset {
+                            map: i,
+                            plane: None,
+                            offset: *x,
+                        }
+                    })?;
+                Ok((i, Gamemaps::parse(header, i)?))
+            })
+            .collect::<Result<Vec<(usize, Gamemaps)>, Wolf3dError>>()?;
+
+        let mut archive = Self {
+            magic: maphead.magic,
+            format: MapFormat::Carmack,
+            data,
+            cache: headers.iter().map(|_| None).collect(),
+            headers,
+        };
+        archive.format = archive.detect_format();
+        Ok(archive)
+    }
+
+    // The first map decides: the format is the first one all of its planes decode with.
+    fn detect_format(&self) -> MapFormat {
+        let Ok((_, header)) = self.header(0) else {
+            return MapFormat::Carmack;
+        };
+        let mut decoder = PlaneDecoder::new();
+        let mut out = vec![0; header.width as usize * header.height as usize];
+
+        MapFormat::ALL
+            .into_iter()
+            .find(|&format| {
+                (0..3).all(|plane| {
+                    self.decode(0, plane, format, &mut decoder, &mut out)
+                        .is_ok()
+                })
+            })
+            .unwrap_or(MapFormat::Carmack)
+    }
+
+    pub fn len(&self) -> usize {
+        self.headers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
+
+    pub fn info(&self, index: usize) -> Option<MapInfo> {
+        self.headers.get(index).map(|(slot, x)| MapInfo {
+            slot: *slot,
+            name: x.name.clone(),
+            width: x.width,
+            height: x.height,
+            plane_lengths: [x.len_plane0, x.len_plane1, x.len_plane2],
+        })
+    }
+
+    pub fn infos(&self) -> Vec<MapInfo> {
+        (0..self.len()).filter_map(|x| self.info(x)).collect()
+    }
+
+    pub fn raw_plane(&self, index: usize, plane: usize) -> Result<&[u8], Wolf3dError> {
+        let (slot, header) = self.header(index)?;
+        let (offset, length) = match plane {
+            0 => (header.off_plane0, header.len_plane0),
+            1 => (header.off_plane1, header.len_plane1),
+            2 => (header.off_plane2, header.len_plane2),
+            _ => (0, 0),
+        };
+
+        // Without the TED5v1.0 signature the first plane can start at offset 0.
+        let signed = self.data.starts_with(super::SIGNATURE);
+        if offset < 0 || (offset == 0 && signed) || offset as usize >= self.data.len() {
+            return Err(Wolf3dError::BadOffset {
+                map: slot,
+                plane: Some(plane),
+                offset,
+            });
+        }
+
+        let position = offset as usize;
+        self.data
+            .get(position..position + length as usize)
+            .ok_or(Wolf3dError::BadPlaneLength {
+                map: slot,
+                plane,
+                length,
+            })
+    }
+
+    pub fn plane(&self, index: usize, plane: usize) -> Result<Plane, Wolf3dError> {
+        let (slot, header) = self.header(index)?;
+        let (width, height) = (header.width as usize, header.height as usize);
+
+        let mut cells = vec![0; width * height];
+        self.plane_into(index, plane, &mut PlaneDecoder::new(), &mut cells)?;
+
+        let actual = cells.len() * 2;
+        Plane::from_vec(width, height, cells).ok_or(Wolf3dError::SizeMismatch {
+            map: slot,
+            plane,
+            expected: width * height * 2,
+            actual,
+        })
+    }
+
+    // Decodes a plane as words into `out`, which must hold width * height words.
+    pub fn plane_into(
+        &self,
+        index: usize,
+        plane: usize,
+        decoder: &mut PlaneDecoder,
+        out: &mut [u16],
+    ) -> Result<(), Wolf3dError> {
+        self.decode(index, plane, self.format, decoder, out)
+    }
+
+    fn decode(
+        &self,
+        index: usize,
+        plane: usize,
+        format: MapFormat,
+        decoder: &mut PlaneDecoder,
+        out: &mut [u16],
+    ) -> Result<(), Wolf3dError> {
+        let (slot, _) = self.header(index)?;
+        let data = self.raw_plane(index, plane)?;
+
+        match format {
+            MapFormat::Carmack => decoder.decode(data, self.magic, out),
+            MapFormat::Rlew => decoder.decode_rlew(data, self.magic, out),
+            MapFormat::Raw => compression::words_into(data, out),
+        }
+        .map_err(|e| Wolf3dError::from_expand(e, slot, plane))
+    }
+
+    pub fn map(&self, index: usize) -> Result<MapData, Wolf3dError> {
+        let (_, header) = self.header(index)?;
+
+        Ok(MapData::new(
+            self.plane(index, 0)?,
+            self.plane(index, 1)?,
+            self.plane(index, 2)?,
+            header.width,
+            header.height,
+            header.name.clone(),
+        ))
+    }
+
+    pub fn get(&mut self, index: usize) -> Result<&MapData, Wolf3dError> {
+        if self.cache.get(index).is_some_and(|x| x.is_none()) {
+            self.cache[index] = Some(self.map(index)?);
+        }
+
+        self.cache
+            .get(index)
+            .and_then(|x| x.as_ref())
+            .ok_or(Wolf3dError::MissingMap(index))
+    }
+
+    pub fn clear_cache(&mut self) {
+        self.cache.iter_mut().for_each(|x| *x = None);
+    }
+
+    pub fn maps(&self) -> Result<Vec<MapData>, Wolf3dError> {
+        (0..self.len()).map(|x| self.map(x)).collect()
+    }
+
+    // Maps and their planes are decoded across threads; the result is in archive order, and
+    // on failure the error of the first failing map is returned, as with `maps`.
+    #[cfg(feature = "parallel")]
+    pub fn par_maps(&self) -> Result<Vec<MapData>, Wolf3dError> {
+        use rayon::prelude::*;
+
+        (0..self.len())
+            .into_par_iter()
+            .map(|i| {
+                let (_, header) = self.header(i)?;
+                let (plane0, (plane1, plane2)) = rayon::join(
+                    || self.plane(i, 0),
+                    || rayon::join(|| self.plane(i, 1), || self.plane(i, 2)),
+                );
+
+                Ok(MapData::new(
+                    plane0?,
+                    plane1?,
+                    plane2?,
+                    header.width,
+                    header.height,
+                    header.name.clone(),
+                ))
+            })
+            .collect::<Vec<Result<MapData, Wolf3dError>>>()
+            .into_iter()
+            .collect()
+    }
+
+    pub fn magic(&self) -> u16 {
+        self.magic
+    }
+
+    pub fn format(&self) -> MapFormat {
+        self.format
+    }
+
+    // Overrides the detected format, for files it guesses wrong.
+    pub fn set_format(&mut self, format: MapFormat) {
+        self.format = format;
+        self.clear_cache();
+    }
+
+    fn header(&self, index: usize) -> Result<(usize, &Gamemaps), Wolf3dError> {
+        self.headers
+            .get(index)
+            .map(|(slot, x)| (*slot, x))
+            .ok_or(Wolf3dError::MissingMap(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wolf3d::compression;
+
+    fn test_archive(maps: &[MapData]) -> MapArchive {
+        let mut maphead = vec![];
+        let mut gamemaps = vec![];
+        super::super::write_gamemaps_to(&mut maphead, &mut gamemaps, maps).unwrap();
+
+        MapArchive::from_bytes(&maphead, &gamemaps).unwrap()
+    }
+
+    // Stores maps the way editors do: planes in `format`, with or without the TED5 signature.
+    fn editor_archive(maps: &[MapData], format: MapFormat, signature: bool) -> MapArchive {
+        let mut data = if signature {
+            super::super::SIGNATURE.to_vec()
+        } else {
+            vec![]
+        };
+        let mut ptr = [0; 100];
+        for (i, map) in maps.iter().enumerate() {
+            let mut offsets = [0; 3];
+            let mut lengths = [0; 3];
+            for (plane, cells) in [&map.plane0, &map.plane1, &map.plane2]
+                .into_iter()
+                .enumerate()
+            {
+                let bytes = cells.to_bytes();
+                let encoded = match format {
+                    MapFormat::Carmack => compression::rlew_compress(&bytes, 0xABCD)
+                        .and_then(|x| compression::carmack_compress(&x))
+                        .unwrap(),
+                    MapFormat::Rlew => compression::rlew_compress(&bytes, 0xABCD).unwrap(),
+                    MapFormat::Raw => bytes,
+                };
+                offsets[plane] = data.len() as i32;
+                lengths[plane] = encoded.len() as u16;
+                data.extend(encoded);
+            }
+
+            ptr[i] = data.len() as i32;
+            Gamemaps::new(
+                offsets[0],
+                offsets[1],
+                offsets[2],
+                lengths[0],
+                lengths[1],
+                lengths[2],
+                map.width,
+                map.height,
+                map.name.clone(),
+            )
+            .write_to(&mut data)
+            .unwrap();
+        }
+
+        let mut maphead = vec![];
+        Maphead::new(0xABCD, ptr).write_to(&mut maphead).unwrap();
+        MapArchive::from_bytes(&maphead, &data).unwrap()
+    }
+
+    #[test]
+    fn test_archive_detects_editor_formats() {
+        let maps = vec![
+            MapData::test_map("Wolf1 Map1", 64, 64, 0),
+            MapData::test_map("Wolf1 Map2", 40, 24, 1),
+        ];
+
+        for format in MapFormat::ALL {
+            for signature in [true, false] {
+                let archive = editor_archive(&maps, format, signature);
+
+                assert_eq!(archive.format(), format, "{:?} {}", format, signature);
+                assert_eq!(archive.maps().unwrap(), maps);
+            }
+        }
+        assert_eq!(test_archive(&maps).format(), MapFormat::Carmack);
+    }
+
+    #[test]
+    fn test_archive_explicit_format() {
+        let maps = vec![MapData::test_map("Wolf1 Map1", 64, 64, 0)];
+        let mut archive = editor_archive(&maps, MapFormat::Rlew, true);
+        archive.get(0).unwrap();
+
+        archive.set_format(MapFormat::Carmack);
+        assert!(archive.cache[0].is_none());
+        assert!(archive.map(0).is_err());
+
+        archive.set_format(MapFormat::Rlew);
+        assert_eq!(archive.map(0).unwrap(), maps[0]);
+        assert_eq!(MapFormat::from_name("RLEW"), Some(MapFormat::Rlew));
+        assert_eq!(MapFormat::from_name("huffman"), None);
+    }
+
+    #[test]
+    fn test_archive_lists_maps_without_decoding() {
+        let maps = vec![
+            MapData::test_map("Wolf1 Map1", 64, 64, 0),
+            MapData::test_map("Wolf1 Map2", 32, 16, 1),
+        ];
+
+        let archive = test_archive(&maps);
+        let infos = archive.infos();
+
+        assert_eq!(archive.len(), 2);
+        assert_eq!(infos[1].slot, 1);
+        assert_eq!(infos[1].name, "Wolf1 Map2");
+        assert_eq!((infos[1].width, infos[1].height), (32, 16));
+        assert!(archive.cache.iter().all(|x| x.is_none()));
+    }
+
+    #[test]
+    fn test_archive_decodes_on_demand() {
+        let maps = vec![
+            MapData::test_map("Wolf1 Map1", 64, 64, 0),
+            MapData::test_map("Wolf1 Map2", 64, 64, 1),
+        ];
+
+        let mut archive = test_archive(&maps);
+
+        assert_eq!(archive.map(1).unwrap(), maps[1]);
+        assert_eq!(archive.get(0).unwrap(), &maps[0]);
+        assert!(archive.cache[0].is_some());
+        assert!(archive.cache[1].is_none());
+        assert!(matches!(archive.map(2), Err(Wolf3dError::MissingMap(2))));
+        assert!(matches!(
+            archive.raw_plane(0, 3),
+            Err(Wolf3dError::BadOffset {
+                map: 0,
+                plane: Some(3),
+                ..
+            })
+        ));
+
+        archive.clear_cache();
+        assert!(archive.cache[0].is_none());
+    }
+
+    #[test]
+    fn test_archive_from_readers() {
+        let maps = vec![MapData::test_map("Wolf1 Map1", 64, 64, 0)];
+        let mut maphead = std::io::Cursor::new(vec![]);
+        let mut gamemaps = std::io::Cursor::new(vec![]);
+        super::super::write_gamemaps_to(&mut maphead, &mut gamemaps, &maps).unwrap();
+
+        let archive = MapArchive::from_readers(maphead, gamemaps).unwrap();
+
+        assert_eq!(archive.maps().unwrap(), maps);
+    }
+
+    #[test]
+    fn test_archive_plane_into_reused_buffer() {
+        let maps = vec![
+            MapData::test_map("Wolf1 Map1", 64, 64, 0),
+            MapData::test_map("Wolf1 Map2", 64, 64, 1),
+        ];
+        let archive = test_archive(&maps);
+        let mut decoder = PlaneDecoder::new();
+        let mut plane = vec![0; 64 * 64];
+
+        for (i, map) in maps.iter().enumerate() {
+            archive.plane_into(i, 1, &mut decoder, &mut plane).unwrap();
+
+            assert_eq!(plane, map.plane1.cells());
+        }
+        assert!(matches!(
+            archive.plane_into(0, 0, &mut decoder, &mut plane[..100]),
+            Err(Wolf3dError::SizeMismatch { map: 0, .. })
+        ));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_archive_par_maps_in_order() {
+        let maps = (0..20u16)
+            .map(|i| MapData::test_map(&format!("Map {}", i), 64, 32, i))
+            .collect::<Vec<MapData>>();
+        let archive = test_archive(&maps);
+
+        assert_eq!(archive.par_maps().unwrap(), maps);
+    }
+
+    #[test]
+    fn test_archive_bad_header_offset() {
+        let mut maphead = vec![0xCD, 0xAB];
+        maphead.extend(1000i32.to_le_bytes());
+        maphead.resize(402, 0);
+
+        let result = MapArchive::from_bytes(&maphead, b"TED5v1.0");
+
+        assert!(matches!(
+            result,
+            Err(Wolf3dError::BadOffset {
+                map: 0,
+                plane: None,
+                offset: 1000
+            })
+        ));
+    }
+
+    // Runs against every data set found in assets/, and passes trivially without one.
+    #[test]
+    fn test_compression_round_trip_shipped_maps() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/assets");
+        for variant in Variant::ALL {
+            let Ok(archive) = MapArchive::open(path, variant) else {
+                continue;
+            };
+            let magic = archive.magic();
+
+            for i in 0..archive.len() {
+                for plane in 0..3 {
+                    let rlew = compression::carmack_expand(archive.raw_plane(i, plane).unwrap());
+                    let expanded = compression::rlew_expand(&rlew.unwrap(), magic).unwrap();
+
+                    let compressed = compression::rlew_compress(&expanded, magic).unwrap();
+                    assert_eq!(
+                        compression::rlew_expand(&compressed, magic).unwrap(),
+                        expanded
+                    );
+
+                    let compressed = compression::carmack_compress(&compressed).unwrap();
+                    let compressed = compression::carmack_expand(&compressed).unwrap();
+                    assert_eq!(
+                        compression::rlew_expand(&compressed, magic).unwrap(),
+                        expanded,
+                        "{:?} map {} plane {}",
+                        variant,
+                        i,
+                        plane
+                    );
+                }
+            }
+        }
+    }
+}
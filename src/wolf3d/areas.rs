@@ -0,0 +1,257 @@
+use super::tiles::{AmbushTile, AreaTile, DoorTile};
+use super::{Grid, Plane};
+
+// Floor tiles carry the number of the area they belong to; rooms hear each other when a door
+// between their areas is open. The engine leaves plane 2 unused, so everything comes from plane 0.
+pub const NUM_AREAS: usize = 37;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AreaDoor {
+    pub x: usize,
+    pub y: usize,
+    pub areas: Option<(u8, u8)>,
+    pub open: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Areas {
+    areas: Grid<Option<u8>>,
+    doors: Vec<AreaDoor>,
+    connections: [[usize; NUM_AREAS]; NUM_AREAS],
+}
+
+impl Areas {
+    pub fn new(plane0: &Plane) -> Self {
+        let area = |x: usize, y: usize| {
+            plane0
+                .get(x, y)
+                .and_then(AreaTile::from_u16)
+                .map(|x| x.number())
+        };
+
+        // Ambush tiles take the area of a neighbouring floor, preferring west, north, south and
+        // then east. SetupGameLevel rewrites them in place while scanning in row order, so the
+        // west and north neighbours are already resolved and areas spread through runs of them.
+        let mut areas: Grid<Option<u8>> = Grid::new(plane0.width(), plane0.height(), None);
+        for (x, y, tile) in plane0.iter_with_coords() {
+            let value = if AmbushTile::from_u16(tile).is_some() {
+                [
+                    x.checked_sub(1).and_then(|x| areas.get(x, y).flatten()),
+                    y.checked_sub(1).and_then(|y| areas.get(x, y).flatten()),
+                    area(x, y + 1),
+                    area(x + 1, y),
+                ]
+                .into_iter()
+                .flatten()
+                .next()
+            } else {
+                area(x, y)
+            };
+            areas.set(x, y, value);
+        }
+
+        // Vertical doors join the areas east and west of them, horizontal ones north and south.
+        let doors = plane0
+            .iter_with_coords()
+            .filter_map(|(x, y, tile)| {
+                let door = DoorTile::from_u16(tile)?;
+                let sides = if door.vertical {
+                    x.checked_sub(1).map(|west| ((west, y), (x + 1, y)))
+                } else {
+                    y.checked_sub(1).map(|north| ((x, north), (x, y + 1)))
+                };
+                let areas = sides.and_then(|((x1, y1), (x2, y2))| {
+                    Some((areas.get(x1, y1)??, areas.get(x2, y2)??))
+                });
+
+                Some(AreaDoor {
+                    x,
+                    y,
+                    areas,
+                    open: false,
+                })
+            })
+            .collect();
+
+        Self {
+            areas,
+            doors,
+            connections: [[0; NUM_AREAS]; NUM_AREAS],
+        }
+    }
+
+    pub fn area(&self, x: usize, y: usize) -> Option<u8> {
+        self.areas.get(x, y).flatten()
+    }
+
+    pub fn doors(&self) -> &[AreaDoor] {
+        &self.doors
+    }
+
+    // Returns false when there is no door at the position.
+    pub fn set_door_open(&mut self, x: usize, y: usize, open: bool) -> bool {
+        let Some(door) = self.doors.iter_mut().find(|d| (d.x, d.y) == (x, y)) else {
+            return false;
+        };
+        if door.open == open {
+            return true;
+        }
+        door.open = open;
+
+        if let Some((a, b)) = door.areas {
+            let (a, b) = (a as usize, b as usize);
+            for (from, to) in [(a, b), (b, a)] {
+                let count = &mut self.connections[from][to];
+                *count = if open {
+                    *count + 1
+                } else {
+                    count.saturating_sub(1)
+                };
+            }
+        }
+        true
+    }
+
+    pub fn connected(&self, from: u8, to: u8) -> bool {
+        self.connected_areas(from)
+            .get(to as usize)
+            .copied()
+            .unwrap_or(false)
+    }
+
+    // Areas reachable from `from` through open doors, as the engine's areabyplayer table.
+    pub fn connected_areas(&self, from: u8) -> [bool; NUM_AREAS] {
+        let mut result = [false; NUM_AREAS];
+        let mut stack = vec![];
+        if (from as usize) < NUM_AREAS {
+            result[from as usize] = true;
+            stack.push(from as usize);
+        }
+
+        while let Some(area) = stack.pop() {
+            for (next, &count) in self.connections[area].iter().enumerate() {
+                if count > 0 && !result[next] {
+                    result[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Three rooms in a row: areas 0, 1 and 2, with a vertical door between the first two and a
+    // horizontal one below the second leading to the third.
+    fn test_plane() -> Plane {
+        #[rustfmt::skip]
+        let cells = vec![
+            1, 1,   1,  1,   1,   1,
+            1, 107, 90, 108, 106, 1,
+            1, 1,   1,  91,  1,   1,
+            1, 1,   1,  109, 109, 1,
+            1, 1,   1,  1,   1,   1,
+        ];
+        Plane::from_vec(6, 5, cells).unwrap()
+    }
+
+    #[test]
+    fn test_area_numbers_and_ambush() {
+        let areas = Areas::new(&test_plane());
+
+        assert_eq!(areas.area(1, 1), Some(0));
+        assert_eq!(areas.area(3, 1), Some(1));
+        assert_eq!(areas.area(4, 1), Some(1));
+        assert_eq!(areas.area(0, 0), None);
+        assert_eq!(areas.area(9, 9), None);
+    }
+
+    // A 2x2 block of ambush tiles that only touches area 1 on its west side, and a door out of it.
+    #[test]
+    fn test_ambush_block_spreads_area() {
+        #[rustfmt::skip]
+        let cells = vec![
+            1, 1,   1,   1,   1,  1,   1,
+            1, 108, 106, 106, 90, 109, 1,
+            1, 1,   106, 106, 1,  1,   1,
+            1, 1,   1,   1,   1,  1,   1,
+        ];
+        let areas = Areas::new(&Plane::from_vec(7, 4, cells).unwrap());
+
+        for (x, y) in [(2, 1), (3, 1), (2, 2), (3, 2)] {
+            assert_eq!(areas.area(x, y), Some(1), "({},{})", x, y);
+        }
+        assert_eq!(areas.doors()[0].areas, Some((1, 2)));
+    }
+
+    // Walling off the winning neighbour one at a time: west, north, south, then east.
+    #[test]
+    fn test_ambush_neighbour_priority() {
+        #[rustfmt::skip]
+        let cells = vec![
+            1, 1,   1,   1,
+            1, 1,   107, 1,
+            1, 108, 106, 109,
+            1, 1,   110, 1,
+            1, 1,   1,   1,
+        ];
+        let mut plane = Plane::from_vec(4, 5, cells).unwrap();
+
+        assert_eq!(Areas::new(&plane).area(2, 2), Some(1));
+        plane.set(1, 2, 1);
+        assert_eq!(Areas::new(&plane).area(2, 2), Some(0));
+        plane.set(2, 1, 1);
+        assert_eq!(Areas::new(&plane).area(2, 2), Some(3));
+        plane.set(2, 3, 1);
+        assert_eq!(Areas::new(&plane).area(2, 2), Some(2));
+    }
+
+    #[test]
+    fn test_doors_join_areas() {
+        let areas = Areas::new(&test_plane());
+
+        assert_eq!(
+            areas.doors(),
+            &[
+                AreaDoor {
+                    x: 2,
+                    y: 1,
+                    areas: Some((0, 1)),
+                    open: false
+                },
+                AreaDoor {
+                    x: 3,
+                    y: 2,
+                    areas: Some((1, 2)),
+                    open: false
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_connections_follow_open_doors() {
+        let mut areas = Areas::new(&test_plane());
+
+        assert!(areas.connected(0, 0));
+        assert!(!areas.connected(0, 1));
+
+        assert!(areas.set_door_open(2, 1, true));
+        assert!(areas.connected(0, 1));
+        assert!(!areas.connected(0, 2));
+
+        areas.set_door_open(3, 2, true);
+        assert!(areas.connected(2, 0));
+        assert_eq!(areas.connected_areas(0)[..4], [true, true, true, false]);
+
+        areas.set_door_open(2, 1, false);
+        areas.set_door_open(2, 1, false);
+        assert!(!areas.connected(0, 1));
+        assert!(areas.connected(1, 2));
+        assert!(!areas.set_door_open(1, 1, true));
+    }
+}
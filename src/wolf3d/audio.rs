@@ -0,0 +1,442 @@
+use std::io::Write;
+
+use byteorder::{LittleEndian, WriteBytesExt};
+
+use super::{Variant, Wolf3dError};
+
+pub const PC_SPEAKER_RATE: u32 = 140;
+pub const DIGITIZED_RATE: u32 = 7042;
+
+const PC_TIMER_FREQUENCY: u32 = 1_193_181;
+const PC_AMPLITUDE: i16 = 8192;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioLayout {
+    pub sounds: usize,
+    pub music: usize,
+}
+
+impl AudioLayout {
+    // Every sound has a PC speaker, an AdLib and a digitized slot, followed by the music tracks.
+    pub fn for_variant(variant: Variant) -> Option<Self> {
+        match variant {
+            Variant::Wolf3dShareware | Variant::Wolf3d => Some(Self {
+                sounds: 87,
+                music: 27,
+            }),
+            Variant::SpearOfDestinyDemo | Variant::SpearOfDestiny => Some(Self {
+                sounds: 81,
+                music: 24,
+            }),
+            Variant::NoahsArk => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChunkKind {
+    PcSpeaker,
+    AdLib,
+    Digitized,
+    Music,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PcSound {
+    pub priority: u16,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Instrument {
+    pub m_char: u8,
+    pub c_char: u8,
+    pub m_scale: u8,
+    pub c_scale: u8,
+    pub m_attack: u8,
+    pub c_attack: u8,
+    pub m_sus: u8,
+    pub c_sus: u8,
+    pub m_wave: u8,
+    pub c_wave: u8,
+    pub n_conn: u8,
+    pub voice: u8,
+    pub mode: u8,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct AdLibSound {
+    pub priority: u16,
+    pub instrument: Instrument,
+    pub block: u8,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ImfCommand {
+    pub register: u8,
+    pub value: u8,
+    pub delay: u16,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Music {
+    pub commands: Vec<ImfCommand>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AudioChunk {
+    PcSpeaker(PcSound),
+    AdLib(AdLibSound),
+    Digitized(Vec<u8>),
+    Music(Music),
+}
+
+#[derive(Debug)]
+pub struct Audio {
+    pub layout: AudioLayout,
+    offsets: Vec<u32>,
+    data: Vec<u8>,
+}
+
+impl Audio {
+    pub fn parse(
+        audiohed: &[u8],
+        audiot: Vec<u8>,
+        layout: AudioLayout,
+    ) -> Result<Self, Wolf3dError> {
+        let offsets = audiohed
+            .chunks_exact(4)
+            .map(|x| u32::from_le_bytes([x[0], x[1], x[2], x[3]]))
+            .collect::<Vec<u32>>();
+        if offsets.len() < layout.sounds * 3 + layout.music + 1 {
+            return Err(Wolf3dError::TruncatedFile("AUDIOHED".to_string()));
+        }
+
+        Ok(Self {
+            layout,
+            offsets,
+            data: audiot,
+        })
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn kind(&self, chunk: usize) -> Option<ChunkKind> {
+        let sounds = self.layout.sounds;
+        match chunk {
+            x if x < sounds => Some(ChunkKind::PcSpeaker),
+            x if x < sounds * 2 => Some(ChunkKind::AdLib),
+            x if x < sounds * 3 => Some(ChunkKind::Digitized),
+            x if x < sounds * 3 + self.layout.music => Some(ChunkKind::Music),
+            _ => None,
+        }
+    }
+
+    pub fn chunk(&self, chunk: usize) -> Result<&[u8], Wolf3dError> {
+        let bad_chunk = || Wolf3dError::BadChunk {
+            file: "AUDIOT".to_string(),
+            chunk,
+        };
+        if chunk >= self.chunk_count() {
+            return Err(bad_chunk());
+        }
+
+        let start = self.offsets[chunk] as usize;
+        let end = self.offsets[chunk + 1] as usize;
+        self.data.get(start..end).ok_or_else(bad_chunk)
+    }
+
+    pub fn decode(&self, chunk: usize) -> Result<AudioChunk, Wolf3dError> {
+        let sounds = self.layout.sounds;
+        match self.kind(chunk) {
+            Some(ChunkKind::PcSpeaker) => Ok(AudioChunk::PcSpeaker(self.pc_sound(chunk)?)),
+            Some(ChunkKind::AdLib) => Ok(AudioChunk::AdLib(self.adlib_sound(chunk - sounds)?)),
+            Some(ChunkKind::Digitized) => Ok(AudioChunk::Digitized(self.chunk(chunk)?.to_vec())),
+            Some(ChunkKind::Music) => Ok(AudioChunk::Music(self.music(chunk - sounds * 3)?)),
+            None => Err(Wolf3dError::BadChunk {
+                file: "AUDIOT".to_string(),
+                chunk,
+            }),
+        }
+    }
+
+    // PC speaker sounds: length (u32), priority (u16), then one timer value per tick.
+    pub fn pc_sound(&self, sound: usize) -> Result<PcSound, Wolf3dError> {
+        let chunk = self.sound_chunk(sound, 0)?;
+        let data = self.chunk(chunk)?;
+        let bad_chunk = || Wolf3dError::BadChunk {
+            file: "AUDIOT".to_string(),
+            chunk,
+        };
+
+        let (length, priority) = sound_header(data).ok_or_else(bad_chunk)?;
+        let data = data.get(6..6 + length).ok_or_else(bad_chunk)?.to_vec();
+
+        Ok(PcSound { priority, data })
+    }
+
+    // AdLib sounds: length (u32), priority (u16), a 16 byte instrument, the octave block, then notes.
+    pub fn adlib_sound(&self, sound: usize) -> Result<AdLibSound, Wolf3dError> {
+        let chunk = self.sound_chunk(sound, 1)?;
+        let data = self.chunk(chunk)?;
+        let bad_chunk = || Wolf3dError::BadChunk {
+            file: "AUDIOT".to_string(),
+            chunk,
+        };
+
+        let (length, priority) = sound_header(data).ok_or_else(bad_chunk)?;
+        let i = data.get(6..22).ok_or_else(bad_chunk)?;
+        let instrument = Instrument {
+            m_char: i[0],
+            c_char: i[1],
+            m_scale: i[2],
+            c_scale: i[3],
+            m_attack: i[4],
+            c_attack: i[5],
+            m_sus: i[6],
+            c_sus: i[7],
+            m_wave: i[8],
+            c_wave: i[9],
+            n_conn: i[10],
+            voice: i[11],
+            mode: i[12],
+        };
+        let block = *data.get(22).ok_or_else(bad_chunk)?;
+        let data = data.get(23..23 + length).ok_or_else(bad_chunk)?.to_vec();
+
+        Ok(AdLibSound {
+            priority,
+            instrument,
+            block,
+            data,
+        })
+    }
+
+    // IMF music: length in bytes (u16), then (register, value, delay) commands.
+    pub fn music(&self, track: usize) -> Result<Music, Wolf3dError> {
+        let chunk = self.layout.sounds * 3 + track;
+        let bad_chunk = || Wolf3dError::BadChunk {
+            file: "AUDIOT".to_string(),
+            chunk,
+        };
+        if track >= self.layout.music {
+            return Err(bad_chunk());
+        }
+
+        let data = self.chunk(chunk)?;
+        let length = data
+            .get(0..2)
+            .map(|x| u16::from_le_bytes([x[0], x[1]]) as usize)
+            .ok_or_else(bad_chunk)?;
+        let commands = data
+            .get(2..2 + length)
+            .ok_or_else(bad_chunk)?
+            .chunks_exact(4)
+            .map(|x| ImfCommand {
+                register: x[0],
+                value: x[1],
+                delay: u16::from_le_bytes([x[2], x[3]]),
+            })
+            .collect();
+
+        Ok(Music { commands })
+    }
+
+    fn sound_chunk(&self, sound: usize, section: usize) -> Result<usize, Wolf3dError> {
+        let chunk = self.layout.sounds * section + sound;
+        if sound >= self.layout.sounds {
+            return Err(Wolf3dError::BadChunk {
+                file: "AUDIOT".to_string(),
+                chunk,
+            });
+        }
+
+        Ok(chunk)
+    }
+}
+
+fn sound_header(data: &[u8]) -> Option<(usize, u16)> {
+    let header = data.get(0..6)?;
+    let length = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let priority = u16::from_le_bytes([header[4], header[5]]);
+    Some((length, priority))
+}
+
+// Each byte drives the speaker for one tick: zero is silence, anything else a timer divisor.
+pub fn render_pc_sound(sound: &PcSound, sample_rate: u32) -> Vec<i16> {
+    let samples_per_tick = (sample_rate / PC_SPEAKER_RATE) as usize;
+    let mut result = Vec::with_capacity(sound.data.len() * samples_per_tick);
+    let mut phase = 0.0;
+
+    for &value in sound.data.iter() {
+        if value == 0 {
+            result.extend(std::iter::repeat_n(0, samples_per_tick));
+            continue;
+        }
+
+        let frequency = PC_TIMER_FREQUENCY as f64 / (value as f64 * 60.0);
+        let step = frequency / sample_rate as f64;
+        for _ in 0..samples_per_tick {
+            result.push(if phase < 0.5 {
+                PC_AMPLITUDE
+            } else {
+                -PC_AMPLITUDE
+            });
+            phase = (phase + step) % 1.0;
+        }
+    }
+
+    result
+}
+
+// Digitized sounds are unsigned 8-bit samples.
+pub fn digitized_to_pcm(data: &[u8]) -> Vec<i16> {
+    data.iter().map(|&x| (x as i16 - 128) << 8).collect()
+}
+
+pub fn write_wav(
+    writer: &mut impl Write,
+    samples: &[i16],
+    sample_rate: u32,
+) -> Result<(), Wolf3dError> {
+    let data_size = samples.len() as u32 * 2;
+
+    writer.write_all(b"RIFF")?;
+    writer.write_u32::<LittleEndian>(36 + data_size)?;
+    writer.write_all(b"WAVE")?;
+
+    writer.write_all(b"fmt ")?;
+    writer.write_u32::<LittleEndian>(16)?;
+    writer.write_u16::<LittleEndian>(1)?;
+    writer.write_u16::<LittleEndian>(1)?;
+    writer.write_u32::<LittleEndian>(sample_rate)?;
+    writer.write_u32::<LittleEndian>(sample_rate * 2)?;
+    writer.write_u16::<LittleEndian>(2)?;
+    writer.write_u16::<LittleEndian>(16)?;
+
+    writer.write_all(b"data")?;
+    writer.write_u32::<LittleEndian>(data_size)?;
+    for &sample in samples {
+        writer.write_i16::<LittleEndian>(sample)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAYOUT: AudioLayout = AudioLayout {
+        sounds: 1,
+        music: 1,
+    };
+
+    fn test_audio() -> Audio {
+        let mut pc = vec![3, 0, 0, 0, 10, 0, 0, 40, 0];
+        pc.push(0);
+
+        let mut adlib = vec![2, 0, 0, 0, 20, 0];
+        adlib.extend(1..=16);
+        adlib.extend([4, 0x50, 0x60, 0]);
+
+        let digitized = vec![];
+
+        let music = vec![8, 0, 0x20, 0x01, 0x05, 0x00, 0xB0, 0x22, 0x00, 0x00];
+
+        let mut audiohed = vec![];
+        let mut audiot = vec![];
+        for chunk in [pc, adlib, digitized, music] {
+            audiohed.extend((audiot.len() as u32).to_le_bytes());
+            audiot.extend(chunk);
+        }
+        audiohed.extend((audiot.len() as u32).to_le_bytes());
+
+        Audio::parse(&audiohed, audiot, LAYOUT).unwrap()
+    }
+
+    #[test]
+    fn test_classify_chunks() {
+        let audio = test_audio();
+
+        assert_eq!(audio.chunk_count(), 4);
+        assert_eq!(audio.kind(0), Some(ChunkKind::PcSpeaker));
+        assert_eq!(audio.kind(1), Some(ChunkKind::AdLib));
+        assert_eq!(audio.kind(2), Some(ChunkKind::Digitized));
+        assert_eq!(audio.kind(3), Some(ChunkKind::Music));
+        assert_eq!(audio.kind(4), None);
+    }
+
+    #[test]
+    fn test_decode_sounds() {
+        let audio = test_audio();
+
+        assert_eq!(
+            audio.pc_sound(0).unwrap(),
+            PcSound {
+                priority: 10,
+                data: vec![0, 40, 0]
+            }
+        );
+
+        let adlib = audio.adlib_sound(0).unwrap();
+        assert_eq!(adlib.priority, 20);
+        assert_eq!(adlib.instrument.m_char, 1);
+        assert_eq!(adlib.instrument.mode, 13);
+        assert_eq!(adlib.block, 4);
+        assert_eq!(adlib.data, vec![0x50, 0x60]);
+    }
+
+    #[test]
+    fn test_decode_music() {
+        let audio = test_audio();
+
+        assert_eq!(
+            audio.decode(3).unwrap(),
+            AudioChunk::Music(Music {
+                commands: vec![
+                    ImfCommand {
+                        register: 0x20,
+                        value: 0x01,
+                        delay: 5
+                    },
+                    ImfCommand {
+                        register: 0xB0,
+                        value: 0x22,
+                        delay: 0
+                    },
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_render_pc_sound() {
+        let audio = test_audio();
+        let sound = audio.pc_sound(0).unwrap();
+
+        let samples = render_pc_sound(&sound, 14000);
+
+        assert_eq!(samples.len(), 300);
+        assert!(samples[..100].iter().all(|&x| x == 0));
+        assert!(samples[100..200].contains(&PC_AMPLITUDE));
+        assert!(samples[100..200].contains(&-PC_AMPLITUDE));
+        assert!(samples[200..].iter().all(|&x| x == 0));
+    }
+
+    #[test]
+    fn test_write_wav() {
+        let mut wav = vec![];
+
+        write_wav(&mut wav, &[1, -1], 8000).unwrap();
+
+        assert_eq!(wav.len(), 48);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(&wav[8..12], b"WAVE");
+        assert_eq!(&wav[40..44], &4u32.to_le_bytes());
+        assert_eq!(&wav[44..], &[0x01, 0x00, 0xFF, 0xFF]);
+    }
+}
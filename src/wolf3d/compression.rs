@@ -1,88 +1,348 @@
-use std::io::BufReader;
+use byteorder::{LittleEndian, WriteBytesExt};
 
-use byteorder::{LittleEndian, ReadBytesExt};
+const NEAR_TAG: u16 = 0xA7;
+const FAR_TAG: u16 = 0xA8;
+const MAX_COPY: usize = 0xFF;
 
-pub fn carmack_expand(compressed: &[u8]) -> Vec<u8> {
-    let mut result = Vec::new();
-    let mut buf = BufReader::new(compressed);
+#[derive(Debug, PartialEq)]
+pub enum ExpandError {
+    Overrun,
+    SizeMismatch { expected: usize, actual: usize },
+    BadReference { position: usize, offset: usize },
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CompressError {
+    OddLength(usize),
+    TooLarge(usize),
+}
+
+// Decodes Carmack compressed, RLEW encoded planes, reusing one scratch buffer between calls.
+#[derive(Debug, Default)]
+pub struct PlaneDecoder {
+    scratch: Vec<u16>,
+}
+
+impl PlaneDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // `out` must be exactly the size of the expanded plane.
+    pub fn decode(
+        &mut self,
+        compressed: &[u8],
+        rlewtag: u16,
+        out: &mut [u16],
+    ) -> Result<(), ExpandError> {
+        let size = Input::new(compressed).word()? as usize / 2;
+        if self.scratch.len() < size {
+            self.scratch.resize(size, 0);
+        }
+
+        let length = carmack_expand_into(compressed, &mut self.scratch)?;
+        let length = rlew_expand_into(&self.scratch[..length], rlewtag, out)?;
+        filled(length, out)
+    }
 
-    let decompressed_size = buf.read_u16::<LittleEndian>().unwrap() as usize;
-    let mut length = decompressed_size / 2;
+    // Planes that are only RLEW encoded, as TED5 saves them in MAPTEMP before compressing.
+    pub fn decode_rlew(
+        &mut self,
+        compressed: &[u8],
+        rlewtag: u16,
+        out: &mut [u16],
+    ) -> Result<(), ExpandError> {
+        self.scratch.clear();
+        self.scratch.extend(
+            compressed
+                .chunks_exact(2)
+                .map(|x| u16::from_le_bytes([x[0], x[1]])),
+        );
+
+        let length = rlew_expand_into(&self.scratch, rlewtag, out)?;
+        filled(length, out)
+    }
+}
+
+// Reads a plane stored as plain little-endian words, with no size prefix.
+pub fn words_into(data: &[u8], out: &mut [u16]) -> Result<(), ExpandError> {
+    if data.len() != out.len() * 2 {
+        return Err(ExpandError::SizeMismatch {
+            expected: out.len() * 2,
+            actual: data.len(),
+        });
+    }
+
+    for (word, x) in out.iter_mut().zip(data.chunks_exact(2)) {
+        *word = u16::from_le_bytes([x[0], x[1]]);
+    }
+    Ok(())
+}
+
+fn filled(length: usize, out: &[u16]) -> Result<(), ExpandError> {
+    if length != out.len() {
+        return Err(ExpandError::SizeMismatch {
+            expected: out.len() * 2,
+            actual: length * 2,
+        });
+    }
 
-    while length > 0 {
-        let ch = buf.read_u16::<LittleEndian>().unwrap();
+    Ok(())
+}
+
+pub fn carmack_expand(compressed: &[u8]) -> Result<Vec<u8>, ExpandError> {
+    let mut result = vec![0; Input::new(compressed).word()? as usize / 2];
+    carmack_expand_into(compressed, &mut result)?;
+
+    Ok(to_bytes(&result))
+}
+
+pub fn rlew_expand(compressed: &[u8], rlewtag: u16) -> Result<Vec<u8>, ExpandError> {
+    let compressed = compressed
+        .chunks_exact(2)
+        .map(|x| u16::from_le_bytes([x[0], x[1]]))
+        .collect::<Vec<u16>>();
+    let mut result = vec![0; *compressed.first().ok_or(ExpandError::Overrun)? as usize / 2];
+    rlew_expand_into(&compressed, rlewtag, &mut result)?;
+
+    Ok(to_bytes(&result))
+}
+
+// Mirrors CAL_CarmackExpand, but rejects references outside the output instead of reading
+// whatever memory they point at. Returns the number of words written.
+pub fn carmack_expand_into(compressed: &[u8], out: &mut [u16]) -> Result<usize, ExpandError> {
+    let mut input = Input::new(compressed);
+
+    let decompressed_size = input.word()? as usize;
+    let length = expanded_length(decompressed_size, out.len())?;
+    let mut outptr = 0;
+
+    while outptr < length {
+        let ch = input.word()?;
         let chhigh = ch >> 8;
-        if chhigh == 0xA7 {
-            let count = ch & 0xFF;
+        if chhigh == NEAR_TAG || chhigh == FAR_TAG {
+            let count = (ch & 0xFF) as usize;
             if count == 0 {
-                let ch = buf.read_u8().unwrap();
-                result.push(ch);
-                length -= 1;
-            } else {
-                let offset = buf.read_u8().unwrap() as usize;
-                let mut copyptr = result.len() - (offset * 2);
-                length -= count as usize;
-                for _ in 0..count * 2 {
-                    result.push(result[copyptr]);
-                    copyptr += 1;
-                }
+                // A literal word whose high byte is a tag; its low byte follows.
+                out[outptr] = (chhigh << 8) | input.byte()? as u16;
+                outptr += 1;
+                continue;
             }
-        } else if chhigh == 0xA8 {
-            let count = ch & 0xFF;
-            if count == 0 {
-                let ch = buf.read_u8().unwrap();
-                result.push(ch);
-                length -= 1;
+
+            let copyptr = if chhigh == NEAR_TAG {
+                let offset = input.byte()? as usize;
+                if offset == 0 || offset > outptr {
+                    return Err(ExpandError::BadReference {
+                        position: outptr * 2,
+                        offset: offset * 2,
+                    });
+                }
+                outptr - offset
             } else {
-                let offset = buf.read_u16::<LittleEndian>().unwrap() as usize;
-                let mut copyptr = offset * 2;
-                length -= count as usize;
-                for _ in 0..count * 2 {
-                    result.push(result[copyptr]);
-                    copyptr += 1;
+                let offset = input.word()? as usize;
+                if offset >= outptr {
+                    return Err(ExpandError::BadReference {
+                        position: outptr * 2,
+                        offset: offset * 2,
+                    });
                 }
+                offset
+            };
+            if count > length - outptr {
+                return Err(ExpandError::SizeMismatch {
+                    expected: decompressed_size,
+                    actual: (outptr + count) * 2,
+                });
+            }
+
+            // Copies may overlap the output, repeating the words just written.
+            for i in 0..count {
+                out[outptr + i] = out[copyptr + i];
             }
+            outptr += count;
         } else {
-            result.push((ch & 0xFF) as u8);
-            result.push((ch >> 8) as u8);
-            length -= 1;
+            out[outptr] = ch;
+            outptr += 1;
         }
     }
 
-    assert_eq!(result.len(), decompressed_size);
+    Ok(length)
+}
+
+// Expands RLEW words, starting with the expanded size in bytes. Returns the number of words
+// written.
+pub fn rlew_expand_into(
+    compressed: &[u16],
+    rlewtag: u16,
+    out: &mut [u16],
+) -> Result<usize, ExpandError> {
+    let mut input = compressed.iter().copied();
+    let mut word = || input.next().ok_or(ExpandError::Overrun);
+
+    let decompressed_size = word()? as usize;
+    let length = expanded_length(decompressed_size, out.len())?;
+    let mut outptr = 0;
 
-    result
+    while outptr < length {
+        let value = word()?;
+        if value != rlewtag {
+            out[outptr] = value;
+            outptr += 1;
+        } else {
+            let count = word()? as usize;
+            let value = word()?;
+            if count > length - outptr {
+                return Err(ExpandError::SizeMismatch {
+                    expected: decompressed_size,
+                    actual: (outptr + count) * 2,
+                });
+            }
+            out[outptr..outptr + count].fill(value);
+            outptr += count;
+        }
+    }
+
+    Ok(length)
+}
+
+fn expanded_length(size: usize, available: usize) -> Result<usize, ExpandError> {
+    if !size.is_multiple_of(2) || size / 2 > available {
+        return Err(ExpandError::SizeMismatch {
+            expected: available * 2,
+            actual: size,
+        });
+    }
+
+    Ok(size / 2)
+}
+
+struct Input<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Input<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn byte(&mut self) -> Result<u8, ExpandError> {
+        let value = *self.data.get(self.position).ok_or(ExpandError::Overrun)?;
+        self.position += 1;
+        Ok(value)
+    }
+
+    fn word(&mut self) -> Result<u16, ExpandError> {
+        Ok(u16::from_le_bytes([self.byte()?, self.byte()?]))
+    }
 }
 
-pub fn rlew_expand(compressed: &[u8], rlewtag: u16) -> Vec<u8> {
+pub fn carmack_compress(data: &[u8]) -> Result<Vec<u8>, CompressError> {
+    let words = to_words(data)?;
     let mut result = Vec::new();
-    let mut buf = BufReader::new(compressed);
+    result.write_u16::<LittleEndian>(data.len() as u16).unwrap();
 
-    let decompressed_size = buf.read_u16::<LittleEndian>().unwrap() as usize;
+    let mut inptr = 0;
+    while inptr < words.len() {
+        let maxlength = MAX_COPY.min(words.len() - inptr);
 
-    while result.len() < decompressed_size {
-        let value = buf.read_u16::<LittleEndian>().unwrap();
-        if value != rlewtag {
-            result.push((value & 0xFF) as u8);
-            result.push((value >> 8) as u8);
+        // A copy needs at least two words to be shorter than the literals.
+        let mut bestlength = 1;
+        let mut bestscan = 0;
+        for inscan in 0..inptr {
+            let mut length = 0;
+            while length < maxlength && words[inscan + length] == words[inptr + length] {
+                length += 1;
+            }
+            if length > bestlength || (length == bestlength && inptr - inscan <= MAX_COPY) {
+                bestlength = length;
+                bestscan = inscan;
+            }
+        }
+
+        let near = inptr - bestscan <= MAX_COPY;
+        if bestlength > 1 && near {
+            result.push(bestlength as u8);
+            result.push(NEAR_TAG as u8);
+            result.push((inptr - bestscan) as u8);
+            inptr += bestlength;
+        } else if bestlength > 2 && bestscan <= u16::MAX as usize {
+            result.push(bestlength as u8);
+            result.push(FAR_TAG as u8);
+            result.write_u16::<LittleEndian>(bestscan as u16).unwrap();
+            inptr += bestlength;
+        } else {
+            let ch = words[inptr];
+            let chhigh = ch >> 8;
+            if chhigh == NEAR_TAG || chhigh == FAR_TAG {
+                // A literal that looks like a tag is written with a count of zero.
+                result.push(0);
+                result.push(chhigh as u8);
+                result.push((ch & 0xFF) as u8);
+            } else {
+                result.write_u16::<LittleEndian>(ch).unwrap();
+            }
+            inptr += 1;
+        }
+    }
+
+    Ok(result)
+}
+
+pub fn rlew_compress(data: &[u8], rlewtag: u16) -> Result<Vec<u8>, CompressError> {
+    let words = to_words(data)?;
+    let mut result = Vec::new();
+    result.write_u16::<LittleEndian>(data.len() as u16).unwrap();
+
+    let mut inptr = 0;
+    while inptr < words.len() {
+        let value = words[inptr];
+        let mut count = 1;
+        while inptr + count < words.len()
+            && words[inptr + count] == value
+            && count < u16::MAX as usize
+        {
+            count += 1;
+        }
+
+        if count > 3 || value == rlewtag {
+            result.write_u16::<LittleEndian>(rlewtag).unwrap();
+            result.write_u16::<LittleEndian>(count as u16).unwrap();
+            result.write_u16::<LittleEndian>(value).unwrap();
         } else {
-            let count = buf.read_u16::<LittleEndian>().unwrap();
-            let value = buf.read_u16::<LittleEndian>().unwrap();
             for _ in 0..count {
-                result.push((value & 0xFF) as u8);
-                result.push((value >> 8) as u8);
+                result.write_u16::<LittleEndian>(value).unwrap();
             }
         }
+        inptr += count;
     }
 
-    assert_eq!(result.len(), decompressed_size);
+    Ok(result)
+}
 
-    result
+fn to_bytes(words: &[u16]) -> Vec<u8> {
+    words.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+fn to_words(data: &[u8]) -> Result<Vec<u16>, CompressError> {
+    if !data.len().is_multiple_of(2) {
+        return Err(CompressError::OddLength(data.len()));
+    }
+    if data.len() > u16::MAX as usize {
+        return Err(CompressError::TooLarge(data.len()));
+    }
+
+    Ok(data
+        .chunks_exact(2)
+        .map(|x| u16::from_le_bytes([x[0], x[1]]))
+        .collect())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
 
     #[test]
     fn test_decompress_carmack_compression_near_pointers() {
@@ -92,7 +352,7 @@ mod tests {
         ];
 
         assert_eq!(
-            carmack_expand(&data),
+            carmack_expand(&data).unwrap(),
             vec![
                 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff, 0xcc, 0xdd, 0xee, 0xff, 0x01, 0x02, 0x03, 0x04,
                 0x05, 0x06
@@ -106,7 +366,8 @@ mod tests {
             carmack_expand(&[
                 22, 0x00, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B,
                 0x04, 0xA7, 0x06, 0x00, 0x01
-            ]),
+            ])
+            .unwrap(),
             [
                 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B, 0x00, 0x01,
                 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x00, 0x01
@@ -121,7 +382,7 @@ mod tests {
             0x03, 0x04, 0x05, 0x06,
         ];
 
-        let result = carmack_expand(&data);
+        let result = carmack_expand(&data).unwrap();
 
         assert_eq!(
             result,
@@ -132,11 +393,98 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_decompress_carmack_tag_literal() {
+        let data = vec![0x04, 0x00, 0x00, 0xa7, 0x12, 0x00, 0xa8, 0x34];
+
+        assert_eq!(carmack_expand(&data).unwrap(), vec![0x12, 0xa7, 0x34, 0xa8]);
+    }
+
+    #[test]
+    fn test_decompress_carmack_bad_references() {
+        let before_start = vec![0x06, 0x00, 0x01, 0x02, 0x02, 0xa7, 0x02];
+        let zero_offset = vec![0x06, 0x00, 0x01, 0x02, 0x02, 0xa7, 0x00];
+        let far_ahead = vec![0x06, 0x00, 0x01, 0x02, 0x02, 0xa8, 0x01, 0x00];
+        let too_long = vec![0x04, 0x00, 0x01, 0x02, 0x05, 0xa7, 0x01];
+
+        assert_eq!(
+            carmack_expand(&before_start),
+            Err(ExpandError::BadReference {
+                position: 2,
+                offset: 4
+            })
+        );
+        assert!(matches!(
+            carmack_expand(&zero_offset),
+            Err(ExpandError::BadReference { .. })
+        ));
+        assert!(matches!(
+            carmack_expand(&far_ahead),
+            Err(ExpandError::BadReference { .. })
+        ));
+        assert!(matches!(
+            carmack_expand(&too_long),
+            Err(ExpandError::SizeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_decompress_carmack_overlapping_copy() {
+        let data = vec![0x08, 0x00, 0x01, 0x02, 0x03, 0xa7, 0x01];
+
+        assert_eq!(carmack_expand(&data).unwrap(), [0x01, 0x02].repeat(4));
+    }
+
+    #[test]
+    fn test_plane_decoder_into_buffer() {
+        let plane = [0x0001u16, 0x0001, 0x0001, 0x0001, 0xA7A8, 0x0002];
+        let data = to_bytes(&plane);
+        let compressed = carmack_compress(&rlew_compress(&data, 0xABCD).unwrap()).unwrap();
+        let mut decoder = PlaneDecoder::new();
+
+        let mut out = [0; 6];
+        decoder.decode(&compressed, 0xABCD, &mut out).unwrap();
+        let mut larger = [0; 8];
+        let result = decoder.decode(&compressed, 0xABCD, &mut larger);
+
+        assert_eq!(out, plane);
+        assert_eq!(
+            result,
+            Err(ExpandError::SizeMismatch {
+                expected: 16,
+                actual: 12
+            })
+        );
+    }
+
+    #[test]
+    fn test_plane_decoder_uncompressed_planes() {
+        let plane = [0x0001u16, 0x0001, 0x0001, 0x0001, 0xA7A8, 0x0002];
+        let data = to_bytes(&plane);
+        let rlew = rlew_compress(&data, 0xABCD).unwrap();
+        let mut decoder = PlaneDecoder::new();
+
+        let mut out = [0; 6];
+        decoder.decode_rlew(&rlew, 0xABCD, &mut out).unwrap();
+        assert_eq!(out, plane);
+
+        let mut out = [0; 6];
+        words_into(&data, &mut out).unwrap();
+        assert_eq!(out, plane);
+        assert_eq!(
+            words_into(&data, &mut [0; 4]),
+            Err(ExpandError::SizeMismatch {
+                expected: 8,
+                actual: 12
+            })
+        );
+    }
+
     #[test]
     fn test_decompress_rlew() {
         let data = vec![0x04, 0x00, 0xFE, 0xFE, 0x02, 0x00, 0x03, 0x04];
 
-        let result = rlew_expand(&data, 0xFEFE);
+        let result = rlew_expand(&data, 0xFEFE).unwrap();
 
         assert_eq!(result, vec![0x03, 0x04, 0x03, 0x04]);
     }
@@ -145,8 +493,135 @@ mod tests {
     fn test_decompress_rlew_flag_word() {
         let data = vec![0x02, 0x00, 0xFE, 0xFE, 0x01, 0x00, 0xFE, 0xFE];
 
-        let result = rlew_expand(&data, 0xFEFE);
+        let result = rlew_expand(&data, 0xFEFE).unwrap();
 
         assert_eq!(result, vec![0xFE, 0xFE]);
     }
+
+    #[test]
+    fn test_decompress_carmack_truncated() {
+        let data = vec![0x04, 0x00, 0xaa, 0xbb];
+
+        assert_eq!(carmack_expand(&data), Err(ExpandError::Overrun));
+    }
+
+    #[test]
+    fn test_decompress_rlew_size_mismatch() {
+        let data = vec![0x04, 0x00, 0xFE, 0xFE, 0x03, 0x00, 0x03, 0x04];
+
+        assert_eq!(
+            rlew_expand(&data, 0xFEFE),
+            Err(ExpandError::SizeMismatch {
+                expected: 4,
+                actual: 6
+            })
+        );
+    }
+
+    #[test]
+    fn test_compress_carmack_near_pointers() {
+        let data = vec![
+            0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff, 0xcc, 0xdd, 0xee, 0xff, 0x01, 0x02, 0x03, 0x04,
+            0x05, 0x06,
+        ];
+
+        let result = carmack_compress(&data).unwrap();
+
+        assert_eq!(
+            result,
+            vec![
+                0x10, 0x00, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff, 0x02, 0xa7, 0x02, 0x01, 0x02, 0x03,
+                0x04, 0x05, 0x06
+            ]
+        );
+        assert_eq!(carmack_expand(&result).unwrap(), data);
+    }
+
+    #[test]
+    fn test_compress_carmack_far_pointers() {
+        let mut data = vec![0x01, 0x02, 0x03, 0x04, 0x05, 0x06];
+        for i in 0..300u16 {
+            data.extend_from_slice(&(i + 0x100).to_le_bytes());
+        }
+        data.extend_from_slice(&[0x01, 0x02, 0x03, 0x04, 0x05, 0x06]);
+
+        let result = carmack_compress(&data).unwrap();
+
+        assert_eq!(&result[result.len() - 4..], &[0x03, 0xa8, 0x00, 0x00]);
+        assert_eq!(carmack_expand(&result).unwrap(), data);
+    }
+
+    #[test]
+    fn test_compress_carmack_tag_literal() {
+        let result = carmack_compress(&[0x12, 0xa7, 0x34, 0xa8]).unwrap();
+
+        assert_eq!(result, vec![0x04, 0x00, 0x00, 0xa7, 0x12, 0x00, 0xa8, 0x34]);
+    }
+
+    #[test]
+    fn test_compress_rlew() {
+        let data = vec![
+            0x03, 0x04, 0x03, 0x04, 0x03, 0x04, 0x03, 0x04, 0x01, 0x00, 0xFE, 0xFE,
+        ];
+
+        let result = rlew_compress(&data, 0xFEFE).unwrap();
+
+        assert_eq!(
+            result,
+            vec![
+                0x0C, 0x00, 0xFE, 0xFE, 0x04, 0x00, 0x03, 0x04, 0x01, 0x00, 0xFE, 0xFE, 0x01, 0x00,
+                0xFE, 0xFE
+            ]
+        );
+        assert_eq!(rlew_expand(&result, 0xFEFE).unwrap(), data);
+    }
+
+    #[test]
+    fn test_compress_odd_length() {
+        assert_eq!(carmack_compress(&[0x01]), Err(CompressError::OddLength(1)));
+        assert_eq!(
+            rlew_compress(&[0x01, 0x02, 0x03], 0xFEFE),
+            Err(CompressError::OddLength(3))
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_carmack_round_trip(words in vec(any::<u16>(), 0..1000)) {
+            let data = words.iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<u8>>();
+
+            let compressed = carmack_compress(&data).unwrap();
+
+            prop_assert_eq!(carmack_expand(&compressed).unwrap(), data);
+        }
+
+        // Small alphabets make copies and runs, including ones built from tag bytes, likely.
+        #[test]
+        fn test_carmack_round_trip_repetitive(
+            words in vec(prop::sample::select(vec![0xA700u16, 0xA8A7, 0x00A8, 0xABCD, 1]), 0..1000)
+        ) {
+            let data = words.iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<u8>>();
+
+            let rlew = rlew_compress(&data, 0xABCD).unwrap();
+            let compressed = carmack_compress(&rlew).unwrap();
+
+            let expanded = carmack_expand(&compressed).unwrap();
+            prop_assert_eq!(rlew_expand(&expanded, 0xABCD).unwrap(), data);
+        }
+
+        #[test]
+        fn test_rlew_round_trip(words in vec(0u16..4, 0..1000), tag in any::<u16>()) {
+            let data = words.iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<u8>>();
+
+            let compressed = rlew_compress(&data, tag).unwrap();
+
+            prop_assert_eq!(rlew_expand(&compressed, tag).unwrap(), data);
+        }
+
+        #[test]
+        fn test_expand_arbitrary_input(data in vec(any::<u8>(), 0..512)) {
+            let _ = carmack_expand(&data);
+            let _ = rlew_expand(&data, 0xABCD);
+        }
+    }
 }
@@ -0,0 +1,277 @@
+use std::fmt;
+
+use super::image::Image;
+use super::tiles::{Direction8, Facing, MapObject, Tile};
+use super::{MapData, Plane, Variant};
+
+const PLANE_COLORS: [[u8; 3]; 3] = [[255, 255, 0], [0, 255, 255], [255, 0, 255]];
+
+// A cell that differs between two maps. Cells that only exist in one of them, because the map
+// was resized, have no value on the other side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Change {
+    pub x: usize,
+    pub y: usize,
+    pub plane: usize,
+    pub old: Option<u16>,
+    pub new: Option<u16>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapDiff {
+    pub variant: Variant,
+    pub old_name: String,
+    pub new_name: String,
+    pub old_size: (usize, usize),
+    pub new_size: (usize, usize),
+    pub changes: Vec<Change>,
+}
+
+impl MapDiff {
+    pub fn new(old: &MapData, new: &MapData, variant: Variant) -> Self {
+        let old_planes = [&old.plane0, &old.plane1, &old.plane2];
+        let new_planes = [&new.plane0, &new.plane1, &new.plane2];
+        let changes = old_planes
+            .into_iter()
+            .zip(new_planes)
+            .enumerate()
+            .flat_map(|(plane, (old, new))| plane_changes(plane, old, new))
+            .collect();
+
+        Self {
+            variant,
+            old_name: old.name.clone(),
+            new_name: new.name.clone(),
+            old_size: (old.plane0.width(), old.plane0.height()),
+            new_size: (new.plane0.width(), new.plane0.height()),
+            changes,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty() && self.old_name == self.new_name && self.old_size == self.new_size
+    }
+
+    pub fn describe(&self, change: &Change) -> String {
+        let value = |value: Option<u16>| match value {
+            Some(value) => describe_value(change.plane, value, self.variant),
+            None => "outside the map".to_string(),
+        };
+        format!(
+            "({},{}) plane{}: {} -> {}",
+            change.x,
+            change.y,
+            change.plane,
+            value(change.old),
+            value(change.new)
+        )
+    }
+
+    // The new map with unchanged tiles dimmed and a frame around every change, coloured by plane:
+    // yellow for tiles, cyan for objects and magenta for plane 2.
+    pub fn overlay(&self, new: &MapData, scale: usize) -> Image {
+        let scale = scale.max(1);
+        let mut image = Image::render(new, self.variant, scale);
+        for pixel in &mut image.pixels {
+            *pixel = pixel.map(|x| x / 3);
+        }
+
+        let mut changed = Plane::new(new.plane0.width(), new.plane0.height(), 0);
+        for change in &self.changes {
+            if let Some(planes) = changed.get(change.x, change.y) {
+                changed.set(change.x, change.y, planes | 1 << change.plane);
+            }
+        }
+
+        let bright = Image::render(new, self.variant, scale);
+        for (x, y, planes) in changed.iter_with_coords() {
+            if planes == 0 {
+                continue;
+            }
+            let color = PLANE_COLORS[planes.trailing_zeros() as usize];
+            for py in y * scale..(y + 1) * scale {
+                for px in x * scale..(x + 1) * scale {
+                    let i = py * image.width + px;
+                    let edge = px == x * scale
+                        || py == y * scale
+                        || px + 1 == (x + 1) * scale
+                        || py + 1 == (y + 1) * scale;
+                    image.pixels[i] = if edge && scale > 2 {
+                        color
+                    } else {
+                        bright.pixels[i]
+                    };
+                }
+            }
+        }
+
+        image
+    }
+}
+
+impl fmt::Display for MapDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.old_name != self.new_name {
+            writeln!(f, "name: {} -> {}", self.old_name, self.new_name)?;
+        }
+        if self.old_size != self.new_size {
+            writeln!(
+                f,
+                "size: {}x{} -> {}x{}",
+                self.old_size.0, self.old_size.1, self.new_size.0, self.new_size.1
+            )?;
+        }
+        for change in &self.changes {
+            writeln!(f, "{}", self.describe(change))?;
+        }
+        Ok(())
+    }
+}
+
+fn plane_changes(plane: usize, old: &Plane, new: &Plane) -> Vec<Change> {
+    let width = old.width().max(new.width());
+    let height = old.height().max(new.height());
+
+    (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .filter_map(|(x, y)| {
+            let (old, new) = (old.get(x, y), new.get(x, y));
+            (old != new).then_some(Change {
+                x,
+                y,
+                plane,
+                old,
+                new,
+            })
+        })
+        .collect()
+}
+
+// Short readable names such as "Door/GoldKey/vertical" or "Guard/medium/N".
+pub fn describe_value(plane: usize, value: u16, variant: Variant) -> String {
+    match plane {
+        0 => match Tile::from_u16(value) {
+            Some(Tile::Empty) => "empty".to_string(),
+            Some(Tile::Wall(wall)) => format!("Wall {}", wall.to_u16()),
+            Some(Tile::Elevator(_)) => "Elevator".to_string(),
+            Some(Tile::Solid(solid)) => format!("Solid {}", solid.to_u16()),
+            Some(Tile::Door(door)) => format!(
+                "Door/{:?}/{}",
+                door.lock,
+                if door.vertical {
+                    "vertical"
+                } else {
+                    "horizontal"
+                }
+            ),
+            Some(Tile::Ambush(_)) => "Ambush".to_string(),
+            Some(Tile::Area(area)) => format!("Area {}", area.number()),
+            None => format!("unknown {}", value),
+        },
+        1 if value == 0 => "nothing".to_string(),
+        1 => match MapObject::from_u16(value, variant) {
+            Some(MapObject::PlayerStart(facing)) => format!("PlayerStart/{}", facing_name(facing)),
+            Some(MapObject::Static(object)) => format!("{:?}", object),
+            Some(MapObject::TurnArrow(direction)) => {
+                format!("TurnArrow/{}", direction_name(direction))
+            }
+            Some(MapObject::Pushwall) => "Pushwall".to_string(),
+            Some(MapObject::VictoryTrigger) => "VictoryTrigger".to_string(),
+            Some(MapObject::DeadGuard) => "DeadGuard".to_string(),
+            Some(MapObject::Enemy(enemy)) => format!(
+                "{:?}/{}/{}{}",
+                enemy.kind,
+                format!("{:?}", enemy.difficulty).to_lowercase(),
+                facing_name(enemy.facing),
+                if enemy.patrol { "/patrol" } else { "" }
+            ),
+            Some(MapObject::Boss(boss)) => format!("{:?}", boss),
+            None => format!("unknown {}", value),
+        },
+        _ => value.to_string(),
+    }
+}
+
+fn facing_name(facing: Facing) -> &'static str {
+    match facing {
+        Facing::North => "N",
+        Facing::East => "E",
+        Facing::South => "S",
+        Facing::West => "W",
+    }
+}
+
+fn direction_name(direction: Direction8) -> &'static str {
+    match direction {
+        Direction8::East => "E",
+        Direction8::NorthEast => "NE",
+        Direction8::North => "N",
+        Direction8::NorthWest => "NW",
+        Direction8::West => "W",
+        Direction8::SouthWest => "SW",
+        Direction8::South => "S",
+        Direction8::SouthEast => "SE",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes_are_decoded() {
+        let mut old = MapData::test_map("Old", 8, 8, 0);
+        let mut new = MapData::test_map("Old", 8, 8, 0);
+        old.plane1.set(2, 3, 145);
+        new.plane0.set(3, 0, 92);
+        new.plane1.set(0, 0, 0);
+        new.plane1.set(2, 3, 199);
+        new.plane2.set(7, 7, 5);
+
+        let diff = MapDiff::new(&old, &new, Variant::Wolf3d);
+
+        assert_eq!(
+            diff.to_string().lines().collect::<Vec<_>>(),
+            vec![
+                "(3,0) plane0: Wall 1 -> Door/GoldKey/vertical",
+                "(0,0) plane1: Puddle -> nothing",
+                "(2,3) plane1: Guard/medium/N -> SS/hard/N",
+                "(7,7) plane2: 0 -> 5",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resized_map() {
+        let old = MapData::test_map("Old", 2, 1, 0);
+        let new = MapData::test_map("New", 3, 1, 0);
+
+        let diff = MapDiff::new(&old, &new, Variant::Wolf3d);
+
+        assert!(!diff.is_empty());
+        assert_eq!(diff.changes.len(), 3);
+        assert_eq!(
+            diff.to_string().lines().take(3).collect::<Vec<_>>(),
+            vec![
+                "name: Old -> New",
+                "size: 2x1 -> 3x1",
+                "(2,0) plane0: outside the map -> Wall 1",
+            ]
+        );
+        assert!(MapDiff::new(&old, &old, Variant::Wolf3d).is_empty());
+    }
+
+    #[test]
+    fn test_overlay_marks_changes() {
+        let old = MapData::test_map("Old", 4, 4, 0);
+        let mut new = MapData::test_map("Old", 4, 4, 0);
+        new.plane1.set(1, 1, 108);
+
+        let image = MapDiff::new(&old, &new, Variant::Wolf3d).overlay(&new, 4);
+        let plain = Image::render(&new, Variant::Wolf3d, 4);
+
+        assert_eq!(image.pixels[4 * 16 + 4], PLANE_COLORS[1]);
+        assert_eq!(image.pixels[5 * 16 + 5], plain.pixels[5 * 16 + 5]);
+        assert_eq!(image.pixels[0], plain.pixels[0].map(|x| x / 3));
+    }
+}
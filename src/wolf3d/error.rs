@@ -0,0 +1,163 @@
+use std::fmt;
+
+use super::compression::ExpandError;
+
+#[derive(Debug)]
+pub enum Wolf3dError {
+    MissingFile(String),
+    Io(std::io::Error),
+    TruncatedMaphead,
+    TruncatedHeader {
+        map: usize,
+    },
+    BadOffset {
+        map: usize,
+        plane: Option<usize>,
+        offset: i32,
+    },
+    BadPlaneLength {
+        map: usize,
+        plane: usize,
+        length: u16,
+    },
+    DecompressionOverrun {
+        map: usize,
+        plane: usize,
+    },
+    SizeMismatch {
+        map: usize,
+        plane: usize,
+        expected: usize,
+        actual: usize,
+    },
+    BadReference {
+        map: usize,
+        plane: usize,
+        position: usize,
+        offset: usize,
+    },
+    InvalidName {
+        map: usize,
+    },
+    TooManyMaps {
+        count: usize,
+    },
+    PlaneTooLarge {
+        map: usize,
+        plane: usize,
+    },
+    TruncatedFile(String),
+    BadChunk {
+        file: String,
+        chunk: usize,
+    },
+    Unsupported(String),
+    MissingMap(usize),
+    Zip(String),
+    Tiled(String),
+}
+
+impl Wolf3dError {
+    pub fn from_expand(error: ExpandError, map: usize, plane: usize) -> Self {
+        match error {
+            ExpandError::Overrun => Self::DecompressionOverrun { map, plane },
+            ExpandError::SizeMismatch { expected, actual } => Self::SizeMismatch {
+                map,
+                plane,
+                expected,
+                actual,
+            },
+            ExpandError::BadReference { position, offset } => Self::BadReference {
+                map,
+                plane,
+                position,
+                offset,
+            },
+        }
+    }
+}
+
+impl fmt::Display for Wolf3dError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingFile(name) => write!(f, "{} not found", name),
+            Self::Io(e) => write!(f, "I/O error: {}", e),
+            Self::TruncatedMaphead => write!(f, "MAPHEAD is truncated"),
+            Self::TruncatedHeader { map } => write!(f, "map {}: header is truncated", map),
+            Self::BadOffset {
+                map,
+                plane: None,
+                offset,
+            } => write!(f, "map {}: header offset {} is out of range", map, offset),
+            Self::BadOffset {
+                map,
+                plane: Some(plane),
+                offset,
+            } => write!(
+                f,
+                "map {} plane {}: offset {} is out of range",
+                map, plane, offset
+            ),
+            Self::BadPlaneLength { map, plane, length } => write!(
+                f,
+                "map {} plane {}: length {} is out of range",
+                map, plane, length
+            ),
+            Self::DecompressionOverrun { map, plane } => write!(
+                f,
+                "map {} plane {}: compressed data ended before the plane was complete",
+                map, plane
+            ),
+            Self::SizeMismatch {
+                map,
+                plane,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "map {} plane {}: expected {} bytes, got {}",
+                map, plane, expected, actual
+            ),
+            Self::BadReference {
+                map,
+                plane,
+                position,
+                offset,
+            } => write!(
+                f,
+                "map {} plane {}: copy at byte {} refers to offset {} outside the output",
+                map, plane, position, offset
+            ),
+            Self::InvalidName { map } => write!(f, "map {}: name is not valid text", map),
+            Self::TooManyMaps { count } => {
+                write!(f, "{} maps do not fit in the 100 MAPHEAD slots", count)
+            }
+            Self::PlaneTooLarge { map, plane } => {
+                write!(f, "map {} plane {}: too large to store", map, plane)
+            }
+            Self::TruncatedFile(name) => write!(f, "{} is truncated", name),
+            Self::BadChunk { file, chunk } => {
+                write!(f, "{} chunk {}: missing or out of range", file, chunk)
+            }
+            Self::Unsupported(what) => write!(f, "{} is not supported", what),
+            Self::MissingMap(index) => write!(f, "map {} does not exist", index),
+            Self::Zip(e) => write!(f, "zip archive error: {}", e),
+            Self::Tiled(e) => write!(f, "Tiled map error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Wolf3dError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Wolf3dError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
@@ -1,3 +1,11 @@
+use std::io::Write;
+
+use byteorder::{LittleEndian, WriteBytesExt};
+
+use super::Wolf3dError;
+
+pub const HEADER_SIZE: usize = 38;
+
 #[derive(Debug)]
 pub struct Gamemaps {
     pub off_plane0: i32,
@@ -12,6 +20,7 @@ pub struct Gamemaps {
 }
 
 impl Gamemaps {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         off_plane0: i32,
         off_plane1: i32,
@@ -36,7 +45,11 @@ impl Gamemaps {
         }
     }
 
-    pub fn parse(data: &[u8]) -> Self {
+    pub fn parse(data: &[u8], map: usize) -> Result<Self, Wolf3dError> {
+        if data.len() < HEADER_SIZE {
+            return Err(Wolf3dError::TruncatedHeader { map });
+        }
+
         let mut off_plane0 = [0; 4];
         off_plane0.copy_from_slice(&data[0..4]);
         let off_plane0 = i32::from_le_bytes(off_plane0);
@@ -69,13 +82,105 @@ impl Gamemaps {
         height.copy_from_slice(&data[20..22]);
         let height = u16::from_le_bytes(height);
 
-        let mut name = [0; 16];
-        name.copy_from_slice(&data[22..38]);
-        let name = String::from_utf8(name.to_vec()).unwrap();
+        let name = &data[22..38];
+        let name = match name.iter().position(|&x| x == 0) {
+            Some(end) => &name[..end],
+            None => name,
+        };
+        let name =
+            String::from_utf8(name.to_vec()).map_err(|_| Wolf3dError::InvalidName { map })?;
 
-        Self::new(
+        Ok(Self::new(
             off_plane0, off_plane1, off_plane2, len_plane0, len_plane1, len_plane2, width, height,
             name,
-        )
+        ))
+    }
+
+    pub fn write_to(&self, writer: &mut impl Write) -> Result<(), Wolf3dError> {
+        writer.write_i32::<LittleEndian>(self.off_plane0)?;
+        writer.write_i32::<LittleEndian>(self.off_plane1)?;
+        writer.write_i32::<LittleEndian>(self.off_plane2)?;
+        writer.write_u16::<LittleEndian>(self.len_plane0)?;
+        writer.write_u16::<LittleEndian>(self.len_plane1)?;
+        writer.write_u16::<LittleEndian>(self.len_plane2)?;
+        writer.write_u16::<LittleEndian>(self.width)?;
+        writer.write_u16::<LittleEndian>(self.height)?;
+
+        // Longer names are cut on a character boundary so they still read back as UTF-8.
+        let mut name = [0; 16];
+        let length = self.name.floor_char_boundary(name.len());
+        name[..length].copy_from_slice(&self.name.as_bytes()[..length]);
+        writer.write_all(&name)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_header() {
+        let mut data = vec![
+            0x0A, 0x00, 0x00, 0x00, 0x14, 0x00, 0x00, 0x00, 0x1E, 0x00, 0x00, 0x00, 0x10, 0x00,
+            0x20, 0x00, 0x30, 0x00, 0x40, 0x00, 0x40, 0x00,
+        ];
+        data.extend_from_slice(b"Wolf1 Map1\0\0\0\0\0\0");
+
+        let header = Gamemaps::parse(&data, 0).unwrap();
+
+        assert_eq!(
+            (header.off_plane0, header.off_plane1, header.off_plane2),
+            (10, 20, 30)
+        );
+        assert_eq!(
+            (header.len_plane0, header.len_plane1, header.len_plane2),
+            (16, 32, 48)
+        );
+        assert_eq!((header.width, header.height), (64, 64));
+        assert_eq!(header.name, "Wolf1 Map1");
+    }
+
+    #[test]
+    fn test_parse_header_errors() {
+        let mut data = vec![0; HEADER_SIZE];
+        data[22] = 0xFF;
+
+        assert!(matches!(
+            Gamemaps::parse(&data[..20], 3),
+            Err(Wolf3dError::TruncatedHeader { map: 3 })
+        ));
+        assert!(matches!(
+            Gamemaps::parse(&data, 4),
+            Err(Wolf3dError::InvalidName { map: 4 })
+        ));
+    }
+
+    #[test]
+    fn test_write_header_round_trip() {
+        let header = Gamemaps::new(11, 22, 33, 1, 2, 3, 64, 32, "Tunnels".to_string());
+        let mut data = vec![];
+
+        header.write_to(&mut data).unwrap();
+        let result = Gamemaps::parse(&data, 0).unwrap();
+
+        assert_eq!(data.len(), HEADER_SIZE);
+        assert_eq!(result.name, "Tunnels");
+        assert_eq!((result.off_plane2, result.len_plane2), (33, 3));
+        assert_eq!((result.width, result.height), (64, 32));
+    }
+
+    #[test]
+    fn test_write_long_multibyte_name() {
+        // "é" is two bytes and would straddle the 16 byte limit at the end.
+        let header = Gamemaps::new(0, 0, 0, 0, 0, 0, 64, 64, "Caserne fortifié".to_string());
+        let mut data = vec![];
+
+        header.write_to(&mut data).unwrap();
+        let result = Gamemaps::parse(&data, 0).unwrap();
+
+        assert_eq!(data.len(), HEADER_SIZE);
+        assert_eq!(result.name, "Caserne fortifi");
     }
 }
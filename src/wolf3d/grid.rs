@@ -0,0 +1,208 @@
+// Cells are stored row by row; coordinates are usize so they can't overflow for any map size.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+pub type Plane = Grid<u16>;
+
+impl<T: Copy> Grid<T> {
+    pub fn new(width: usize, height: usize, value: T) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+
+    pub fn from_vec(width: usize, height: usize, cells: Vec<T>) -> Option<Self> {
+        (width.checked_mul(height)? == cells.len()).then_some(Self {
+            width,
+            height,
+            cells,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<T> {
+        self.index(x, y).map(|i| self.cells[i])
+    }
+
+    // Returns the previous value, or None when the position is outside the grid.
+    pub fn set(&mut self, x: usize, y: usize, value: T) -> Option<T> {
+        let i = self.index(x, y)?;
+        Some(std::mem::replace(&mut self.cells[i], value))
+    }
+
+    pub fn fill(&mut self, value: T) {
+        self.cells.fill(value);
+    }
+
+    // The cells north, east, south and west of a position that lie inside the grid.
+    pub fn neighbors(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize, T)> + '_ {
+        [
+            (Some(x), y.checked_sub(1)),
+            (x.checked_add(1), Some(y)),
+            (Some(x), y.checked_add(1)),
+            (x.checked_sub(1), Some(y)),
+        ]
+        .into_iter()
+        .filter_map(move |position| match position {
+            (Some(x), Some(y)) => self.get(x, y).map(|value| (x, y, value)),
+            _ => None,
+        })
+    }
+
+    // Cells reachable from a position through neighbours that `passable` accepts. The start is
+    // always included when it lies inside the grid.
+    pub fn flood_fill(
+        &self,
+        x: usize,
+        y: usize,
+        passable: impl Fn(usize, usize, T) -> bool,
+    ) -> Grid<bool> {
+        let mut result = Grid::new(self.width, self.height, false);
+        if result.set(x, y, true).is_none() {
+            return result;
+        }
+
+        let mut stack = vec![(x, y)];
+        while let Some((x, y)) = stack.pop() {
+            for (x, y, value) in self.neighbors(x, y) {
+                if passable(x, y, value) && result.set(x, y, true) == Some(false) {
+                    stack.push((x, y));
+                }
+            }
+        }
+        result
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks_exact(self.width.max(1))
+    }
+
+    pub fn iter_with_coords(&self) -> impl Iterator<Item = (usize, usize, T)> + '_ {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(i, &value)| (i % self.width, i / self.width, value))
+    }
+
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    fn index(&self, x: usize, y: usize) -> Option<usize> {
+        self.contains(x, y).then(|| y * self.width + x)
+    }
+}
+
+impl Plane {
+    pub fn from_bytes(width: usize, height: usize, data: &[u8]) -> Option<Self> {
+        if data.len() != width.checked_mul(height)?.checked_mul(2)? {
+            return None;
+        }
+
+        let cells = data
+            .chunks_exact(2)
+            .map(|x| u16::from_le_bytes([x[0], x[1]]))
+            .collect();
+        Self::from_vec(width, height, cells)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.cells.iter().flat_map(|x| x.to_le_bytes()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_grid() -> Grid<u16> {
+        Grid::from_vec(3, 2, vec![1, 2, 3, 4, 5, 6]).unwrap()
+    }
+
+    #[test]
+    fn test_get_and_set() {
+        let mut grid = test_grid();
+
+        assert_eq!(grid.get(2, 1), Some(6));
+        assert_eq!(grid.get(3, 0), None);
+        assert_eq!(grid.get(0, 2), None);
+        assert_eq!(grid.set(1, 0, 9), Some(2));
+        assert_eq!(grid.set(0, 5, 9), None);
+        assert_eq!(grid.cells(), &[1, 9, 3, 4, 5, 6]);
+        assert_eq!(Grid::from_vec(3, 3, vec![0; 6]), None);
+    }
+
+    #[test]
+    fn test_neighbors_stay_inside() {
+        let grid = test_grid();
+
+        assert_eq!(
+            grid.neighbors(0, 0).collect::<Vec<_>>(),
+            vec![(1, 0, 2), (0, 1, 4)]
+        );
+        assert_eq!(
+            grid.neighbors(1, 1).collect::<Vec<_>>(),
+            vec![(1, 0, 2), (2, 1, 6), (0, 1, 4)]
+        );
+        assert_eq!(grid.neighbors(usize::MAX, usize::MAX).count(), 0);
+    }
+
+    #[test]
+    fn test_flood_fill() {
+        let grid = Grid::from_vec(3, 3, vec![0, 1, 0, 0, 1, 0, 0, 0, 0]).unwrap();
+
+        let filled = grid.flood_fill(0, 0, |_, _, value| value == 0);
+        assert_eq!(filled.cells().iter().filter(|&&x| x).count(), 7);
+        assert_eq!(filled.get(1, 0), Some(false));
+
+        let walled = grid.flood_fill(0, 0, |x, _, value| value == 0 && x == 0);
+        assert_eq!(
+            walled.cells(),
+            &[true, false, false, true, false, false, true, false, false]
+        );
+        assert!(
+            !grid
+                .flood_fill(5, 5, |_, _, _| true)
+                .cells()
+                .contains(&true)
+        );
+    }
+
+    #[test]
+    fn test_rows_and_coords() {
+        let grid = test_grid();
+
+        assert_eq!(
+            grid.rows().collect::<Vec<_>>(),
+            vec![&[1, 2, 3][..], &[4, 5, 6][..]]
+        );
+        assert_eq!(grid.iter_with_coords().nth(4), Some((1, 1, 5)));
+        assert_eq!(Grid::new(0, 0, 0u16).rows().count(), 0);
+    }
+
+    #[test]
+    fn test_plane_bytes_round_trip() {
+        let plane = Plane::from_bytes(2, 1, &[0x34, 0x12, 0xCD, 0xAB]).unwrap();
+
+        assert_eq!(plane.cells(), &[0x1234, 0xABCD]);
+        assert_eq!(plane.to_bytes(), vec![0x34, 0x12, 0xCD, 0xAB]);
+        assert_eq!(Plane::from_bytes(2, 2, &[0; 4]), None);
+    }
+}
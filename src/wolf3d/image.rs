@@ -0,0 +1,221 @@
+use std::io::Write;
+
+use super::tiles::{Difficulty, DoorLock, DoorTile, MapObject, StaticObject, Tile};
+use super::{MapData, Variant, Wolf3dError};
+
+const EMPTY: [u8; 3] = [0, 0, 0];
+const UNKNOWN: [u8; 3] = [255, 0, 255];
+const FLOOR: [u8; 3] = [48, 48, 56];
+const AMBUSH: [u8; 3] = [72, 48, 48];
+const ELEVATOR: [u8; 3] = [0, 200, 80];
+const SOLID: [u8; 3] = [96, 96, 96];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<[u8; 3]>,
+}
+
+impl Image {
+    // Every tile becomes a scale x scale block; objects are drawn as a smaller block on top.
+    pub fn render(map: &MapData, variant: Variant, scale: usize) -> Self {
+        let scale = scale.max(1);
+        let margin = scale / 4;
+        let width = map.plane0.width() * scale;
+        let mut pixels = vec![EMPTY; width * map.plane0.height() * scale];
+
+        let mut fill = |x: usize, y: usize, inset: usize, color: [u8; 3]| {
+            for py in y * scale + inset..(y + 1) * scale - inset {
+                let row = py * width;
+                pixels[row + x * scale + inset..row + (x + 1) * scale - inset].fill(color);
+            }
+        };
+
+        for (x, y, tile) in map.plane0.iter_with_coords() {
+            fill(x, y, 0, tile_color(tile));
+        }
+        // Objects outside plane 0, on a map whose planes differ in size, have no tile to sit on.
+        for (x, y, value) in map.plane1.iter_with_coords() {
+            if !map.plane0.contains(x, y) {
+                continue;
+            }
+            if let Some(color) = object_color(value, variant) {
+                fill(x, y, margin, color);
+            }
+        }
+
+        Self {
+            width,
+            height: map.plane0.height() * scale,
+            pixels,
+        }
+    }
+
+    pub fn write_ppm(&self, writer: &mut impl Write) -> Result<(), Wolf3dError> {
+        write!(writer, "P6\n{} {}\n255\n", self.width, self.height)?;
+        writer.write_all(&self.pixels.concat())?;
+        Ok(())
+    }
+
+    #[cfg(feature = "png")]
+    pub fn write_png(&self, writer: impl Write) -> Result<(), Wolf3dError> {
+        let mut encoder = png::Encoder::new(writer, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        encoder
+            .write_header()
+            .and_then(|mut x| x.write_image_data(&self.pixels.concat()))
+            .map_err(|e| Wolf3dError::Io(std::io::Error::other(e)))
+    }
+}
+
+// Walls get a muted colour of their own so neighbouring textures stay distinguishable.
+pub fn tile_color(tile: u16) -> [u8; 3] {
+    match Tile::from_u16(tile) {
+        Some(Tile::Empty) => EMPTY,
+        Some(Tile::Wall(wall)) => hsv((wall.to_u16() as u32 * 47) % 360, 40, 60),
+        Some(Tile::Elevator(_)) => ELEVATOR,
+        Some(Tile::Solid(_)) => SOLID,
+        Some(Tile::Door(DoorTile { lock, .. })) => match lock {
+            DoorLock::Normal => [150, 100, 40],
+            DoorLock::GoldKey => [230, 190, 40],
+            DoorLock::SilverKey => [190, 190, 210],
+            DoorLock::Lock3 | DoorLock::Lock4 => [140, 60, 140],
+            DoorLock::Elevator => [0, 160, 60],
+        },
+        Some(Tile::Ambush(_)) => AMBUSH,
+        Some(Tile::Area(_)) => FLOOR,
+        None => UNKNOWN,
+    }
+}
+
+// Enemies get darker the harder the difficulty they first appear on.
+pub fn object_color(value: u16, variant: Variant) -> Option<[u8; 3]> {
+    Some(match MapObject::from_u16(value, variant)? {
+        MapObject::PlayerStart(_) => [60, 120, 255],
+        MapObject::Enemy(enemy) => match enemy.difficulty {
+            Difficulty::Easy => [255, 140, 140],
+            Difficulty::Medium => [230, 40, 40],
+            Difficulty::Hard => [140, 0, 30],
+        },
+        MapObject::Boss(_) => [255, 0, 200],
+        MapObject::DeadGuard => [120, 60, 60],
+        MapObject::Static(object) if object.is_treasure() => [255, 220, 0],
+        MapObject::Static(object) if is_pickup(object) => [0, 220, 220],
+        MapObject::Static(_) => [150, 150, 120],
+        MapObject::Pushwall => [255, 128, 0],
+        MapObject::TurnArrow(_) => [90, 90, 140],
+        MapObject::VictoryTrigger => [255, 255, 255],
+    })
+}
+
+fn is_pickup(object: StaticObject) -> bool {
+    matches!(
+        object,
+        StaticObject::GoldKey
+            | StaticObject::SilverKey
+            | StaticObject::BadFood
+            | StaticObject::Food
+            | StaticObject::FirstAid
+            | StaticObject::Clip
+            | StaticObject::DroppedClip
+            | StaticObject::Clip25
+            | StaticObject::MachineGun
+            | StaticObject::ChainGun
+            | StaticObject::ExtraLife
+            | StaticObject::SpearOfDestiny
+    )
+}
+
+// Hue in degrees, saturation and value in percent.
+fn hsv(hue: u32, saturation: u32, value: u32) -> [u8; 3] {
+    let max = value * 255 / 100;
+    let min = max * (100 - saturation) / 100;
+    let rise = min + (max - min) * (hue % 60) / 60;
+    let fall = max - (max - min) * (hue % 60) / 60;
+
+    let (r, g, b) = match hue / 60 {
+        0 => (max, rise, min),
+        1 => (fall, max, min),
+        2 => (min, max, rise),
+        3 => (min, fall, max),
+        4 => (rise, min, max),
+        _ => (max, min, fall),
+    };
+    [r as u8, g as u8, b as u8]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wolf3d::Plane;
+
+    fn test_map() -> MapData {
+        let plane0 = Plane::from_vec(3, 1, vec![1, 90, 107]).unwrap();
+        let plane1 = Plane::from_vec(3, 1, vec![0, 0, 180]).unwrap();
+        MapData::new(
+            plane0,
+            plane1,
+            Plane::new(3, 1, 0),
+            3,
+            1,
+            "Test".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_render_scaled_tiles_and_objects() {
+        let image = Image::render(&test_map(), Variant::Wolf3d, 4);
+
+        assert_eq!((image.width, image.height), (12, 4));
+        assert_eq!(image.pixels[0], tile_color(1));
+        assert_eq!(image.pixels[4], [150, 100, 40]);
+        // The hard guard covers the middle of its floor tile.
+        assert_eq!(image.pixels[8], FLOOR);
+        assert_eq!(image.pixels[12 + 9], [140, 0, 30]);
+    }
+
+    #[test]
+    fn test_render_larger_plane1() {
+        let mut map = test_map();
+        map.plane1 = Plane::new(5, 3, 180);
+
+        let image = Image::render(&map, Variant::Wolf3d, 4);
+
+        assert_eq!((image.width, image.height), (12, 4));
+        assert_eq!(image.pixels[12 + 1], [140, 0, 30]);
+    }
+
+    #[test]
+    fn test_wall_colors_differ() {
+        assert_ne!(tile_color(1), tile_color(2));
+        assert_eq!(tile_color(104), SOLID);
+        assert_eq!(tile_color(200), UNKNOWN);
+        assert_eq!(hsv(0, 100, 100), [255, 0, 0]);
+        assert_eq!(hsv(240, 100, 100), [0, 0, 255]);
+    }
+
+    #[test]
+    fn test_write_ppm() {
+        let image = Image::render(&test_map(), Variant::Wolf3d, 1);
+        let mut data = vec![];
+
+        image.write_ppm(&mut data).unwrap();
+
+        assert!(data.starts_with(b"P6\n3 1\n255\n"));
+        assert_eq!(data.len(), 11 + 9);
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn test_write_png() {
+        let image = Image::render(&test_map(), Variant::Wolf3d, 2);
+        let mut data = vec![];
+
+        image.write_png(&mut data).unwrap();
+
+        assert!(data.starts_with(b"\x89PNG\r\n\x1a\n"));
+    }
+}
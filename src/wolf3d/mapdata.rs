@@ -1,12 +1,11 @@
-use std::io::BufReader;
+use super::tiles::{DoorLock, DoorTile, Enemy, EnemyKind, MapObject, StaticObject, Tile};
+use super::{Plane, Variant};
 
-use byteorder::{LittleEndian, ReadBytesExt};
-
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct MapData {
-    pub plane0: Vec<u8>,
-    pub plane1: Vec<u8>,
-    pub plane2: Vec<u8>,
+    pub plane0: Plane,
+    pub plane1: Plane,
+    pub plane2: Plane,
     pub width: u16,
     pub height: u16,
     pub name: String,
@@ -14,9 +13,9 @@ pub struct MapData {
 
 impl MapData {
     pub fn new(
-        plane0: Vec<u8>,
-        plane1: Vec<u8>,
-        plane2: Vec<u8>,
+        plane0: Plane,
+        plane1: Plane,
+        plane2: Plane,
         width: u16,
         height: u16,
         name: String,
@@ -31,54 +30,208 @@ impl MapData {
         }
     }
 
-    pub fn print(self: &Self) {
-        fn get_plane0_value(value: u16) -> char {
-            match value {
-                0..=63 => '🧱',
-                90..=91 => '🚪',
-                92..=95 => '🔒',
-                100..=101 => '🔚',
-                106..=143 => '🟦',
-                _ => ' ',
-            }
-        }
+    #[cfg(test)]
+    pub fn test_map(name: &str, width: u16, height: u16, seed: u16) -> Self {
+        let plane = |f: &dyn Fn(u16, u16) -> u16| {
+            let cells = (0..height)
+                .flat_map(|y| (0..width).map(move |x| f(x, y)))
+                .collect();
+            Plane::from_vec(width as usize, height as usize, cells).unwrap()
+        };
+        Self::new(
+            plane(&|x, y| {
+                if x == 0 || y == 0 || x == width - 1 || y == height - 1 {
+                    1 + seed % 8
+                } else {
+                    108 + (x / 8)
+                }
+            }),
+            plane(&|x, y| {
+                if (x + y + seed).is_multiple_of(13) {
+                    23 + y % 4
+                } else {
+                    0
+                }
+            }),
+            plane(&|_, _| 0),
+            width,
+            height,
+            name.to_string(),
+        )
+    }
 
-        fn get_plane1_value(value: u16) -> Option<char> {
-            match value {
-                19..=22 => Some('🔹'),
-                29 => Some('🦴'),
-                43 => Some('🗝'),
-                44 => Some('🔑'),
-                47 => Some('🍗'),
-                48 => Some('🩹'),
-                49 => Some('📦'),
-                50 => Some('🔫'),
-                51 => Some('💯'),
-                52..=55 => Some('💰'),
-                56 => Some('💟'),
-                23..=70 => Some('🏺'),
-                98 => Some('🔳'),
-                124 => Some('💀'),
-                134..=141 => Some('🐕'),
-                108..=227 => Some('👨'),
-                _ => None,
-            }
+    pub fn player_starts(&self, variant: Variant) -> Vec<(usize, usize)> {
+        self.plane1
+            .iter_with_coords()
+            .filter(|&(_, _, x)| {
+                matches!(
+                    MapObject::from_u16(x, variant),
+                    Some(MapObject::PlayerStart(_))
+                )
+            })
+            .map(|(x, y, _)| (x, y))
+            .collect()
+    }
+
+    // The engine spawns the player at the last start it finds.
+    pub fn player_start(&self, variant: Variant) -> Option<(usize, usize)> {
+        self.player_starts(variant).last().copied()
+    }
+
+    // Floor, doors and secret pushwalls can be walked through, ignoring locks.
+    pub fn is_passable(&self, x: usize, y: usize, variant: Variant) -> bool {
+        match self.plane0.get(x, y).and_then(Tile::from_u16) {
+            Some(Tile::Door(_)) => true,
+            Some(tile) if tile.is_floor() => true,
+            _ => self
+                .plane1
+                .get(x, y)
+                .is_some_and(|x| MapObject::from_u16(x, variant) == Some(MapObject::Pushwall)),
         }
+    }
 
-        println!("=========== Name: {} ===========", self.name);
-        let mut plane0buf = BufReader::new(&self.plane0[..]);
-        let mut plane1buf = BufReader::new(&self.plane1[..]);
-        for _ in 0..self.height {
-            for _ in 0..self.width {
-                let p0 = get_plane0_value(plane0buf.read_u16::<LittleEndian>().unwrap());
-                let p1 = get_plane1_value(plane1buf.read_u16::<LittleEndian>().unwrap());
-                if p1.is_some() {
-                    print!("{}", p1.unwrap());
-                } else {
-                    print!("{}", p0);
+    // One emoji per tile, objects drawn over the floor.
+    pub fn to_emoji(&self, variant: Variant) -> String {
+        self.render(
+            |value| match Tile::from_u16(value) {
+                Some(Tile::Empty | Tile::Wall(_) | Tile::Elevator(_) | Tile::Solid(_)) => '🧱',
+                Some(Tile::Door(DoorTile {
+                    lock: DoorLock::Normal,
+                    ..
+                })) => '🚪',
+                Some(Tile::Door(DoorTile {
+                    lock: DoorLock::Elevator,
+                    ..
+                })) => '🔚',
+                Some(Tile::Door(_)) => '🔒',
+                Some(Tile::Ambush(_) | Tile::Area(_)) => '🟦',
+                None => ' ',
+            },
+            |value| match MapObject::from_u16(value, variant)? {
+                MapObject::PlayerStart(_) => Some('🔹'),
+                MapObject::Static(object) => Some(match object {
+                    StaticObject::BadFood => '🦴',
+                    StaticObject::GoldKey => '🗝',
+                    StaticObject::SilverKey => '🔑',
+                    StaticObject::Food => '🍗',
+                    StaticObject::FirstAid => '🩹',
+                    StaticObject::Clip | StaticObject::DroppedClip => '📦',
+                    StaticObject::MachineGun => '🔫',
+                    StaticObject::ChainGun => '💯',
+                    StaticObject::ExtraLife => '💟',
+                    x if x.is_treasure() => '💰',
+                    _ => '🏺',
+                }),
+                MapObject::Pushwall => Some('🔳'),
+                MapObject::DeadGuard => Some('💀'),
+                MapObject::Enemy(Enemy {
+                    kind: EnemyKind::Dog,
+                    ..
+                }) => Some('🐕'),
+                MapObject::Enemy(_) | MapObject::Boss(_) => Some('👨'),
+                MapObject::TurnArrow(_) | MapObject::VictoryTrigger => None,
+            },
+        )
+    }
+
+    // The same layout in plain ASCII, for terminals and diffs that can't take emoji.
+    pub fn to_text(&self, variant: Variant) -> String {
+        self.render(
+            |value| match Tile::from_u16(value) {
+                Some(Tile::Empty) => ' ',
+                Some(Tile::Wall(_) | Tile::Solid(_)) => '#',
+                Some(Tile::Elevator(_)) => 'X',
+                Some(Tile::Door(DoorTile {
+                    lock: DoorLock::Normal,
+                    vertical,
+                })) => {
+                    if vertical {
+                        '|'
+                    } else {
+                        '-'
+                    }
                 }
+                Some(Tile::Door(DoorTile {
+                    lock: DoorLock::Elevator,
+                    ..
+                })) => 'E',
+                Some(Tile::Door(_)) => 'L',
+                Some(Tile::Ambush(_)) => ',',
+                Some(Tile::Area(_)) => '.',
+                None => '?',
+            },
+            |value| {
+                Some(match MapObject::from_u16(value, variant)? {
+                    MapObject::PlayerStart(_) => '@',
+                    MapObject::Static(StaticObject::GoldKey | StaticObject::SilverKey) => 'k',
+                    MapObject::Static(x) if x.is_treasure() => '$',
+                    MapObject::Static(
+                        StaticObject::BadFood
+                        | StaticObject::Food
+                        | StaticObject::FirstAid
+                        | StaticObject::Clip
+                        | StaticObject::DroppedClip
+                        | StaticObject::MachineGun
+                        | StaticObject::ChainGun
+                        | StaticObject::ExtraLife,
+                    ) => '*',
+                    MapObject::Static(_) => 'o',
+                    MapObject::Pushwall => 'P',
+                    MapObject::DeadGuard => '%',
+                    MapObject::Enemy(enemy) => match enemy.kind {
+                        EnemyKind::Guard => 'G',
+                        EnemyKind::Officer => 'O',
+                        EnemyKind::SS => 'S',
+                        EnemyKind::Dog => 'D',
+                        EnemyKind::Mutant => 'M',
+                    },
+                    MapObject::Boss(_) => 'B',
+                    MapObject::TurnArrow(_) | MapObject::VictoryTrigger => return None,
+                })
+            },
+        )
+    }
+
+    fn render(&self, tile: impl Fn(u16) -> char, object: impl Fn(u16) -> Option<char>) -> String {
+        let mut result = String::new();
+        for (row0, row1) in self.plane0.rows().zip(self.plane1.rows()) {
+            for (&p0, &p1) in row0.iter().zip(row1) {
+                result.push(object(p1).unwrap_or_else(|| tile(p0)));
             }
-            println!();
+            result.push('\n');
         }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_text_draws_objects_over_tiles() {
+        let plane0 = Plane::from_vec(4, 2, vec![1, 108, 90, 101, 21, 106, 92, 0]).unwrap();
+        let plane1 = Plane::from_vec(4, 2, vec![0, 19, 0, 0, 0, 108, 0, 43]).unwrap();
+        let map = MapData::new(plane0, plane1, Plane::new(4, 2, 0), 4, 2, "Test".into());
+
+        assert_eq!(map.to_text(Variant::Wolf3d), "#@|E\nXGLk\n");
+        assert_eq!(map.to_emoji(Variant::Wolf3d).lines().count(), 2);
+    }
+
+    #[test]
+    fn test_player_start_and_passable() {
+        let mut map = MapData::test_map("Test", 8, 8, 0);
+        map.plane1.fill(0);
+        map.plane1.set(2, 2, 19);
+        map.plane1.set(5, 3, 21);
+        map.plane1.set(0, 4, 98);
+
+        assert_eq!(map.player_starts(Variant::Wolf3d), vec![(2, 2), (5, 3)]);
+        assert_eq!(map.player_start(Variant::Wolf3d), Some((5, 3)));
+        assert_eq!(map.player_start(Variant::NoahsArk), None);
+        assert!(map.is_passable(1, 1, Variant::Wolf3d));
+        assert!(map.is_passable(0, 4, Variant::Wolf3d));
+        assert!(!map.is_passable(0, 3, Variant::Wolf3d));
+        assert!(!map.is_passable(8, 0, Variant::Wolf3d));
     }
 }
@@ -1,5 +1,7 @@
-use byteorder::{LittleEndian, ReadBytesExt};
-use std::{fs::File, io::BufReader};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{BufReader, Read, Write};
+
+use super::Wolf3dError;
 
 #[derive(Debug)]
 pub struct Maphead {
@@ -12,16 +14,59 @@ impl Maphead {
         Self { magic, ptr }
     }
 
-    pub fn from_file(file: File) -> Result<Self, Box<dyn std::error::Error>> {
-        let mut buf = BufReader::new(file);
+    pub fn from_reader(reader: impl Read) -> Result<Self, Wolf3dError> {
+        let mut buf = BufReader::new(reader);
 
-        let magic = buf.read_u16::<LittleEndian>()?;
+        let magic = buf.read_u16::<LittleEndian>().map_err(truncated)?;
 
         let mut ptr = [0; 100];
-        for i in 0..100 {
-            ptr[i] = buf.read_i32::<LittleEndian>()?;
+        for p in ptr.iter_mut() {
+            *p = buf.read_i32::<LittleEndian>().map_err(truncated)?;
         }
 
         Ok(Self::new(magic, ptr))
     }
+
+    pub fn write_to(&self, writer: &mut impl Write) -> Result<(), Wolf3dError> {
+        writer.write_u16::<LittleEndian>(self.magic)?;
+        for p in self.ptr {
+            writer.write_i32::<LittleEndian>(p)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn truncated(error: std::io::Error) -> Wolf3dError {
+    match error.kind() {
+        std::io::ErrorKind::UnexpectedEof => Wolf3dError::TruncatedMaphead,
+        _ => Wolf3dError::Io(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maphead_from_bytes() {
+        let mut data = vec![0xCD, 0xAB, 0x08, 0x00, 0x00, 0x00];
+        data.resize(402, 0);
+
+        let maphead = Maphead::from_reader(&data[..]).unwrap();
+
+        assert_eq!(maphead.magic, 0xABCD);
+        assert_eq!(maphead.ptr[0], 8);
+        assert!(maphead.ptr[1..].iter().all(|&x| x == 0));
+    }
+
+    #[test]
+    fn test_maphead_truncated() {
+        let data = [0xCD, 0xAB, 0x08, 0x00];
+
+        assert!(matches!(
+            Maphead::from_reader(&data[..]),
+            Err(Wolf3dError::TruncatedMaphead)
+        ));
+    }
 }
@@ -0,0 +1,123 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::Wolf3dError;
+
+// Data files are looked up by name, ignoring case, in a directory or a zip archive.
+pub fn is_zip(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|x| x.eq_ignore_ascii_case("zip"))
+        && Path::new(path).is_file()
+}
+
+pub fn exists(path: &str, name: &str) -> bool {
+    if is_zip(path) {
+        return zip_entry(path, name).is_some();
+    }
+
+    find_file(path, name).is_some()
+}
+
+pub fn read_file(path: &str, name: &str) -> Result<Vec<u8>, Wolf3dError> {
+    if is_zip(path) {
+        return read_zip_entry(path, name);
+    }
+
+    let file_path = find_file(path, name).ok_or(Wolf3dError::MissingFile(name.to_string()))?;
+    Ok(fs::read(file_path)?)
+}
+
+pub fn find_file(path: &str, name: &str) -> Option<PathBuf> {
+    let exact = Path::new(path).join(name);
+    if exact.is_file() {
+        return Some(exact);
+    }
+
+    fs::read_dir(path)
+        .ok()?
+        .filter_map(|x| x.ok())
+        .find(|x| x.file_name().to_string_lossy().eq_ignore_ascii_case(name))
+        .map(|x| x.path())
+}
+
+// Entries may sit in a folder inside the archive, so only the file name is compared.
+#[cfg(feature = "zip")]
+fn zip_entry(path: &str, name: &str) -> Option<String> {
+    let archive = zip::ZipArchive::new(fs::File::open(path).ok()?).ok()?;
+    archive
+        .file_names()
+        .find(|x| {
+            x.rsplit(['/', '\\'])
+                .next()
+                .is_some_and(|x| x.eq_ignore_ascii_case(name))
+        })
+        .map(|x| x.to_string())
+}
+
+#[cfg(feature = "zip")]
+fn read_zip_entry(path: &str, name: &str) -> Result<Vec<u8>, Wolf3dError> {
+    use std::io::Read;
+
+    let entry = zip_entry(path, name).ok_or(Wolf3dError::MissingFile(name.to_string()))?;
+    let mut archive =
+        zip::ZipArchive::new(fs::File::open(path)?).map_err(|e| Wolf3dError::Zip(e.to_string()))?;
+    let mut file = archive
+        .by_name(&entry)
+        .map_err(|e| Wolf3dError::Zip(e.to_string()))?;
+
+    let mut data = vec![];
+    file.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+#[cfg(not(feature = "zip"))]
+fn zip_entry(_path: &str, _name: &str) -> Option<String> {
+    None
+}
+
+#[cfg(not(feature = "zip"))]
+fn read_zip_entry(_path: &str, _name: &str) -> Result<Vec<u8>, Wolf3dError> {
+    Err(Wolf3dError::Unsupported(
+        "zip archives (build with the zip feature)".to_string(),
+    ))
+}
+
+#[cfg(all(test, feature = "zip"))]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+    use crate::wolf3d::{MapData, Variant};
+
+    #[test]
+    fn test_read_maps_from_zip() {
+        let maps = vec![MapData::test_map("Wolf1 Map1", 64, 64, 0)];
+        let mut maphead = vec![];
+        let mut gamemaps = vec![];
+        crate::wolf3d::write_gamemaps_to(&mut maphead, &mut gamemaps, &maps).unwrap();
+
+        let path = std::env::temp_dir().join(format!("roguestein-{}.ZIP", std::process::id()));
+        let mut zip = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        zip.start_file("wolf3d/maphead.sod", options).unwrap();
+        zip.write_all(&maphead).unwrap();
+        zip.start_file("wolf3d/GameMaps.Sod", options).unwrap();
+        zip.write_all(&gamemaps).unwrap();
+        zip.finish().unwrap();
+
+        let path_str = path.to_str().unwrap();
+        let variant = Variant::detect(path_str);
+        let result = crate::wolf3d::read_gamemaps(path_str, Variant::SpearOfDestiny);
+        let missing = read_file(path_str, "VSWAP.SOD");
+
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(variant.unwrap(), Variant::SpearOfDestiny);
+        assert_eq!(result.unwrap(), maps);
+        assert!(matches!(missing, Err(Wolf3dError::MissingFile(_))));
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+
+use super::tiles::{Difficulty, DoorLock, DoorTile, EnemyKind, MapObject, StaticObject, Tile};
+use super::{MapData, Variant};
+
+// The totals the engine counts while spawning a level (ScanInfoPlane), which the intermission
+// screen turns into kill, secret and treasure ratios, plus what the player can pick up.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MapStats {
+    pub enemies: HashMap<EnemyKind, usize>,
+    pub bosses: usize,
+    pub secrets: usize,
+    pub treasures: usize,
+    pub treasure_score: u32,
+    pub gold_keys: usize,
+    pub silver_keys: usize,
+    pub locked_doors: usize,
+    pub ammo: usize,
+    pub weapons: usize,
+    pub health: usize,
+    pub extra_lives: usize,
+    pub floor: usize,
+    pub reachable_floor: usize,
+}
+
+impl MapStats {
+    pub fn new(map: &MapData, variant: Variant, difficulty: Difficulty) -> Self {
+        let mut stats = Self::default();
+
+        for &tile in map.plane0.cells() {
+            match Tile::from_u16(tile) {
+                Some(x) if x.is_floor() => stats.floor += 1,
+                Some(Tile::Door(DoorTile { lock, .. }))
+                    if !matches!(lock, DoorLock::Normal | DoorLock::Elevator) =>
+                {
+                    stats.locked_doors += 1
+                }
+                _ => {}
+            }
+        }
+
+        for &value in map.plane1.cells() {
+            match MapObject::from_u16(value, variant) {
+                Some(MapObject::Enemy(enemy)) if enemy.difficulty <= difficulty => {
+                    *stats.enemies.entry(enemy.kind).or_default() += 1
+                }
+                Some(MapObject::Boss(_)) => stats.bosses += 1,
+                Some(MapObject::Pushwall) => stats.secrets += 1,
+                Some(MapObject::Static(object)) => stats.add_static(object),
+                _ => {}
+            }
+        }
+
+        stats.reachable_floor = reachable_floor(map, variant);
+        stats
+    }
+
+    fn add_static(&mut self, object: StaticObject) {
+        match object {
+            StaticObject::Cross => self.treasure_score += 100,
+            StaticObject::Chalice => self.treasure_score += 500,
+            StaticObject::Bible => self.treasure_score += 1000,
+            StaticObject::Crown => self.treasure_score += 5000,
+            _ => {}
+        }
+
+        match object {
+            // The engine counts a full heal as treasure even though it scores nothing.
+            x if x.is_treasure() => self.treasures += 1,
+            StaticObject::ExtraLife => {
+                self.treasures += 1;
+                self.extra_lives += 1;
+            }
+            StaticObject::GoldKey => self.gold_keys += 1,
+            StaticObject::SilverKey => self.silver_keys += 1,
+            StaticObject::Clip | StaticObject::DroppedClip | StaticObject::Clip25 => self.ammo += 1,
+            StaticObject::MachineGun | StaticObject::ChainGun => self.weapons += 1,
+            StaticObject::BadFood | StaticObject::Food | StaticObject::FirstAid => self.health += 1,
+            _ => {}
+        }
+    }
+
+    // Every enemy and boss counts towards the kill ratio.
+    pub fn kills(&self) -> usize {
+        self.enemies.values().sum::<usize>() + self.bosses
+    }
+
+    pub fn enemy_count(&self, kind: EnemyKind) -> usize {
+        self.enemies.get(&kind).copied().unwrap_or(0)
+    }
+}
+
+// The percentage shown on the intermission screen, which is 0 when there was nothing to find.
+pub fn ratio(found: usize, total: usize) -> usize {
+    found * 100 / total.max(1)
+}
+
+// Floor tiles reachable from the player start, walking through doors and secret pushwalls.
+fn reachable_floor(map: &MapData, variant: Variant) -> usize {
+    let Some((x, y)) = map.player_start(variant) else {
+        return 0;
+    };
+
+    let reachable = map
+        .plane0
+        .flood_fill(x, y, |x, y, _| map.is_passable(x, y, variant));
+    map.plane0
+        .iter_with_coords()
+        .filter(|&(x, y, tile)| {
+            reachable.get(x, y) == Some(true) && Tile::from_u16(tile).is_some_and(|x| x.is_floor())
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wolf3d::Plane;
+
+    // Two rooms joined by a gold door, a secret room behind a pushwall and a sealed-off cell.
+    fn test_map() -> MapData {
+        #[rustfmt::skip]
+        let plane0 = vec![
+            1, 1,   1,   1,   1, 1,   1,
+            1, 108, 108, 92,  109, 109, 1,
+            1, 108, 108, 108, 1, 109, 1,
+            1, 1,   1,   1,   110, 1, 1,
+            1, 111, 1,   1,   1, 1,   1,
+            1, 1,   1,   1,   1, 1,   1,
+        ];
+        #[rustfmt::skip]
+        let plane1 = vec![
+            0, 0,   0,   0,  0,   0,  0,
+            0, 19,  108, 0,  144, 43, 0,
+            0, 214, 52,  48, 0,   49, 0,
+            0, 98,  0,   0,  188, 0,  0,
+            0, 55,  0,   0,  0,   0,  0,
+            0, 0,   0,   0,  0,   0,  0,
+        ];
+
+        MapData::new(
+            Plane::from_vec(7, 6, plane0).unwrap(),
+            Plane::from_vec(7, 6, plane1).unwrap(),
+            Plane::new(7, 6, 0),
+            7,
+            6,
+            "Stats".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_totals() {
+        let stats = MapStats::new(&test_map(), Variant::Wolf3d, Difficulty::Hard);
+
+        assert_eq!(stats.enemy_count(EnemyKind::Guard), 2);
+        assert_eq!(stats.enemy_count(EnemyKind::Officer), 1);
+        assert_eq!(stats.enemy_count(EnemyKind::Dog), 0);
+        assert_eq!((stats.bosses, stats.kills()), (1, 4));
+        assert_eq!((stats.treasures, stats.treasure_score), (2, 5100));
+        assert_eq!(
+            (stats.secrets, stats.gold_keys, stats.locked_doors),
+            (1, 1, 1)
+        );
+        assert_eq!((stats.ammo, stats.health, stats.weapons), (1, 1, 0));
+        assert_eq!((stats.floor, stats.reachable_floor), (10, 9));
+    }
+
+    #[test]
+    fn test_enemies_follow_difficulty() {
+        let map = test_map();
+        let easy = MapStats::new(&map, Variant::Wolf3d, Difficulty::Easy);
+        let medium = MapStats::new(&map, Variant::Wolf3d, Difficulty::Medium);
+
+        assert_eq!(easy.kills(), 2);
+        assert_eq!(medium.kills(), 3);
+        assert_eq!(medium.enemy_count(EnemyKind::Officer), 0);
+    }
+
+    #[test]
+    fn test_ratio() {
+        assert_eq!(ratio(1, 3), 33);
+        assert_eq!(ratio(3, 3), 100);
+        assert_eq!(ratio(0, 0), 0);
+    }
+}
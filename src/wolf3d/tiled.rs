@@ -0,0 +1,954 @@
+use std::io::Write;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::image::{Image, tile_color};
+use super::tiles::MapObject;
+use super::{Grid, MapData, Plane, Variant, Wolf3dError};
+
+// Tiled reserves global tile id 0 for an empty cell, so map words are stored shifted up by one
+// and the tileset has a tile for every possible word.
+const FIRST_GID: u32 = 1;
+const FLIP_FLAGS: u32 = 0xF000_0000;
+const TILESET_COLUMNS: u32 = 256;
+const TILE_COUNT: u32 = 65536;
+const PLANE_LAYERS: [&str; 3] = ["plane0", "plane1", "plane2"];
+const OBJECT_LAYER: &str = "objects";
+
+pub const TILE_SIZE: u32 = 8;
+pub const TILESET_IMAGE: &str = "wolf3d-tiles.png";
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TiledMap {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub version: String,
+    pub orientation: String,
+    pub renderorder: String,
+    pub width: usize,
+    pub height: usize,
+    pub tilewidth: u32,
+    pub tileheight: u32,
+    pub infinite: bool,
+    pub nextlayerid: u32,
+    pub nextobjectid: u32,
+    pub properties: Vec<Property>,
+    pub tilesets: Vec<Tileset>,
+    pub layers: Vec<Layer>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tileset {
+    pub firstgid: u32,
+    pub name: String,
+    pub tilewidth: u32,
+    pub tileheight: u32,
+    pub tilecount: u32,
+    pub columns: u32,
+    pub image: String,
+    pub imagewidth: u32,
+    pub imageheight: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Layer {
+    TileLayer(TileLayer),
+    ObjectGroup(ObjectGroup),
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TileLayer {
+    pub id: u32,
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: usize,
+    pub height: usize,
+    pub opacity: f64,
+    pub visible: bool,
+    pub data: Vec<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ObjectGroup {
+    pub id: u32,
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+    pub opacity: f64,
+    pub visible: bool,
+    pub locked: bool,
+    pub draworder: String,
+    pub objects: Vec<Object>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Object {
+    pub id: u32,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub class: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub rotation: f64,
+    pub visible: bool,
+    pub properties: Vec<Property>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Property {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub value: Value,
+}
+
+impl Property {
+    fn new(name: &str, kind: &str, value: impl Into<Value>) -> Self {
+        Self {
+            name: name.to_string(),
+            kind: kind.to_string(),
+            value: value.into(),
+        }
+    }
+}
+
+impl TiledMap {
+    // One tile layer per plane, except that plane 1 values the variant decodes become objects in
+    // their own layer, where they can be moved and edited. Everything else stays a tile.
+    pub fn from_map(map: &MapData, variant: Variant) -> Self {
+        let mut plane1 = map.plane1.clone();
+        for (x, y, value) in map.plane1.iter_with_coords() {
+            if MapObject::from_u16(value, variant).is_some() {
+                plane1.set(x, y, 0);
+            }
+        }
+
+        let mut layers: Vec<Layer> = [&map.plane0, &plane1, &map.plane2]
+            .into_iter()
+            .zip(PLANE_LAYERS)
+            .enumerate()
+            .map(|(i, (plane, name))| {
+                Layer::TileLayer(TileLayer {
+                    id: i as u32 + 1,
+                    name: name.to_string(),
+                    x: 0,
+                    y: 0,
+                    width: plane.width(),
+                    height: plane.height(),
+                    opacity: 1.0,
+                    visible: true,
+                    data: plane
+                        .iter_with_coords()
+                        .map(|(x, y, value)| match i {
+                            1 if plane.get(x, y) != map.plane1.get(x, y) => 0,
+                            _ => value as u32 + FIRST_GID,
+                        })
+                        .collect(),
+                })
+            })
+            .collect();
+
+        let objects: Vec<Object> = map
+            .plane1
+            .iter_with_coords()
+            .filter_map(|(x, y, value)| Some((x, y, value, MapObject::from_u16(value, variant)?)))
+            .zip(1..)
+            .map(|((x, y, value, object), id)| {
+                let (name, class, mut properties) = describe(object);
+                properties.insert(0, Property::new("value", "int", value));
+                Object {
+                    id,
+                    name,
+                    class: class.to_string(),
+                    x: (x as u32 * TILE_SIZE) as f64,
+                    y: (y as u32 * TILE_SIZE) as f64,
+                    width: TILE_SIZE as f64,
+                    height: TILE_SIZE as f64,
+                    rotation: 0.0,
+                    visible: true,
+                    properties,
+                }
+            })
+            .collect();
+        let nextobjectid = objects.len() as u32 + 1;
+
+        layers.push(Layer::ObjectGroup(ObjectGroup {
+            id: 4,
+            name: OBJECT_LAYER.to_string(),
+            x: 0,
+            y: 0,
+            opacity: 1.0,
+            visible: true,
+            locked: false,
+            draworder: "topdown".to_string(),
+            objects,
+        }));
+
+        Self {
+            kind: "map".to_string(),
+            version: "1.10".to_string(),
+            orientation: "orthogonal".to_string(),
+            renderorder: "right-down".to_string(),
+            width: map.width as usize,
+            height: map.height as usize,
+            tilewidth: TILE_SIZE,
+            tileheight: TILE_SIZE,
+            infinite: false,
+            nextlayerid: 5,
+            nextobjectid,
+            properties: vec![
+                Property::new("name", "string", map.name.clone()),
+                Property::new("variant", "string", variant.extension()),
+            ],
+            tilesets: vec![Tileset {
+                firstgid: FIRST_GID,
+                name: "wolf3d".to_string(),
+                tilewidth: TILE_SIZE,
+                tileheight: TILE_SIZE,
+                tilecount: TILE_COUNT,
+                columns: TILESET_COLUMNS,
+                image: TILESET_IMAGE.to_string(),
+                imagewidth: TILESET_COLUMNS * TILE_SIZE,
+                imageheight: TILE_COUNT / TILESET_COLUMNS * TILE_SIZE,
+            }],
+            layers,
+        }
+    }
+
+    // Plane 2 may be left out and comes back empty. Objects are written into plane 1.
+    pub fn to_map(&self) -> Result<MapData, Wolf3dError> {
+        let error = |message: String| Wolf3dError::Tiled(message);
+        let width = u16::try_from(self.width)
+            .map_err(|_| error(format!("width {} is too large", self.width)))?;
+        let height = u16::try_from(self.height)
+            .map_err(|_| error(format!("height {} is too large", self.height)))?;
+        let first_gid = self.tilesets.first().map_or(FIRST_GID, |x| x.firstgid);
+
+        let plane = |name: &str| {
+            let layer = self.layers.iter().find_map(|layer| match layer {
+                Layer::TileLayer(layer) if layer.name == name => Some(layer),
+                _ => None,
+            });
+            let Some(layer) = layer else {
+                return Ok(None);
+            };
+
+            let cells = layer
+                .data
+                .iter()
+                .map(|&gid| match gid & !FLIP_FLAGS {
+                    0 => Some(0),
+                    gid => gid
+                        .checked_sub(first_gid)
+                        .and_then(|x| u16::try_from(x).ok()),
+                })
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| error(format!("{}: tile outside the map tileset", name)))?;
+            let count = cells.len();
+
+            Plane::from_vec(self.width, self.height, cells)
+                .map(Some)
+                .ok_or_else(|| {
+                    error(format!(
+                        "{}: expected {} tiles, got {}",
+                        name,
+                        self.width * self.height,
+                        count
+                    ))
+                })
+        };
+
+        let plane0 =
+            plane(PLANE_LAYERS[0])?.ok_or_else(|| error("missing layer plane0".to_string()))?;
+        let mut plane1 =
+            plane(PLANE_LAYERS[1])?.ok_or_else(|| error("missing layer plane1".to_string()))?;
+        self.place_objects(&mut plane1)?;
+        let plane2 =
+            plane(PLANE_LAYERS[2])?.unwrap_or_else(|| Plane::new(self.width, self.height, 0));
+        let name = self
+            .properties
+            .iter()
+            .find(|x| x.name == "name")
+            .and_then(|x| x.value.as_str())
+            .unwrap_or_default();
+
+        Ok(MapData::new(
+            plane0,
+            plane1,
+            plane2,
+            width,
+            height,
+            name.to_string(),
+        ))
+    }
+
+    // An object's value property decides what it is. The decoded properties are checked against
+    // it so that editing only those is reported rather than lost, and an object may not cover a
+    // different plane 1 tile or another object.
+    fn place_objects(&self, plane1: &mut Plane) -> Result<(), Wolf3dError> {
+        let variant = self
+            .properties
+            .iter()
+            .find(|x| x.name == "variant")
+            .and_then(|x| x.value.as_str())
+            .and_then(Variant::from_extension);
+        let tile_width = self.tilewidth.max(1) as f64;
+        let tile_height = self.tileheight.max(1) as f64;
+        let mut placed = Grid::new(plane1.width(), plane1.height(), false);
+
+        let objects = self.layers.iter().flat_map(|layer| match layer {
+            Layer::ObjectGroup(group) if group.name == OBJECT_LAYER => group.objects.iter(),
+            _ => [].iter(),
+        });
+        for object in objects {
+            let error = |message: String| {
+                Wolf3dError::Tiled(format!(
+                    "object {} ({}): {}",
+                    object.id, object.name, message
+                ))
+            };
+
+            let value = object
+                .properties
+                .iter()
+                .find(|x| x.name == "value")
+                .and_then(|x| x.value.as_u64())
+                .and_then(|x| u16::try_from(x).ok())
+                .ok_or_else(|| error("no valid value property".to_string()))?;
+            let (x, y) = (
+                (object.x / tile_width).floor(),
+                (object.y / tile_height).floor(),
+            );
+            if x < 0.0 || y < 0.0 || !plane1.contains(x as usize, y as usize) {
+                return Err(error("outside the map".to_string()));
+            }
+            let (x, y) = (x as usize, y as usize);
+
+            if placed.get(x, y) == Some(true) {
+                return Err(error(format!("another object is at ({},{})", x, y)));
+            }
+            match plane1.get(x, y) {
+                Some(0) => {}
+                Some(tile) if tile == value => {}
+                Some(tile) => {
+                    return Err(error(format!(
+                        "plane1 holds {} at ({},{}), not {}",
+                        tile, x, y, value
+                    )));
+                }
+                None => {}
+            }
+            if let Some(decoded) = variant.and_then(|x| MapObject::from_u16(value, x)) {
+                let (_, _, expected) = describe(decoded);
+                let changed = expected.iter().find(|expected| {
+                    object
+                        .properties
+                        .iter()
+                        .any(|x| x.name == expected.name && x.value != expected.value)
+                });
+                if let Some(changed) = changed {
+                    return Err(error(format!(
+                        "{} doesn't match value {}; change the value property instead",
+                        changed.name, value
+                    )));
+                }
+            }
+
+            plane1.set(x, y, value);
+            placed.set(x, y, true);
+        }
+
+        Ok(())
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, Wolf3dError> {
+        serde_json::from_str(text).map_err(|e| Wolf3dError::Tiled(e.to_string()))
+    }
+
+    pub fn write_json(&self, writer: &mut impl Write) -> Result<(), Wolf3dError> {
+        serde_json::to_writer_pretty(&mut *writer, self)
+            .map_err(|e| Wolf3dError::Tiled(e.to_string()))?;
+        writeln!(writer)?;
+        Ok(())
+    }
+
+    // Only CSV encoded layer data is read, which is what write_tmx produces.
+    pub fn from_tmx(text: &str) -> Result<Self, Wolf3dError> {
+        let document =
+            roxmltree::Document::parse(text).map_err(|e| Wolf3dError::Tiled(e.to_string()))?;
+        let root = document.root_element();
+        if root.tag_name().name() != "map" {
+            return Err(Wolf3dError::Tiled("root element is not <map>".to_string()));
+        }
+
+        let mut map = Self {
+            kind: "map".to_string(),
+            version: root.attribute("version").unwrap_or_default().to_string(),
+            orientation: root
+                .attribute("orientation")
+                .unwrap_or_default()
+                .to_string(),
+            renderorder: root
+                .attribute("renderorder")
+                .unwrap_or_default()
+                .to_string(),
+            width: attribute(root, "width")?,
+            height: attribute(root, "height")?,
+            tilewidth: attribute(root, "tilewidth")?,
+            tileheight: attribute(root, "tileheight")?,
+            infinite: root.attribute("infinite") == Some("1"),
+            nextlayerid: optional_attribute(root, "nextlayerid"),
+            nextobjectid: optional_attribute(root, "nextobjectid"),
+            properties: properties(root),
+            tilesets: vec![],
+            layers: vec![],
+        };
+
+        for node in root.children().filter(|x| x.is_element()) {
+            match node.tag_name().name() {
+                "tileset" => {
+                    let image = node.children().find(|x| x.has_tag_name("image"));
+                    map.tilesets.push(Tileset {
+                        firstgid: attribute(node, "firstgid")?,
+                        name: node.attribute("name").unwrap_or_default().to_string(),
+                        tilewidth: optional_attribute(node, "tilewidth"),
+                        tileheight: optional_attribute(node, "tileheight"),
+                        tilecount: optional_attribute(node, "tilecount"),
+                        columns: optional_attribute(node, "columns"),
+                        image: image
+                            .and_then(|x| x.attribute("source"))
+                            .unwrap_or_default()
+                            .to_string(),
+                        imagewidth: image.map_or(0, |x| optional_attribute(x, "width")),
+                        imageheight: image.map_or(0, |x| optional_attribute(x, "height")),
+                    });
+                }
+                "layer" => {
+                    let data = node
+                        .children()
+                        .find(|x| x.has_tag_name("data"))
+                        .ok_or_else(|| Wolf3dError::Tiled("<layer> has no <data>".to_string()))?;
+                    if data.attribute("encoding") != Some("csv") {
+                        return Err(Wolf3dError::Unsupported(
+                            "TMX layer data that is not CSV encoded".to_string(),
+                        ));
+                    }
+
+                    map.layers.push(Layer::TileLayer(TileLayer {
+                        id: optional_attribute(node, "id"),
+                        name: node.attribute("name").unwrap_or_default().to_string(),
+                        x: 0,
+                        y: 0,
+                        width: attribute(node, "width")?,
+                        height: attribute(node, "height")?,
+                        opacity: node
+                            .attribute("opacity")
+                            .and_then(|x| x.parse().ok())
+                            .unwrap_or(1.0),
+                        visible: node.attribute("visible") != Some("0"),
+                        data: data
+                            .text()
+                            .unwrap_or_default()
+                            .split(',')
+                            .map(|x| x.trim().parse())
+                            .collect::<Result<_, _>>()
+                            .map_err(|e| Wolf3dError::Tiled(format!("<data>: {}", e)))?,
+                    }));
+                }
+                "objectgroup" => {
+                    let objects = node
+                        .children()
+                        .filter(|x| x.has_tag_name("object"))
+                        .map(|object| Object {
+                            id: optional_attribute(object, "id"),
+                            name: object.attribute("name").unwrap_or_default().to_string(),
+                            class: object
+                                .attribute("type")
+                                .or(object.attribute("class"))
+                                .unwrap_or_default()
+                                .to_string(),
+                            x: optional_attribute(object, "x"),
+                            y: optional_attribute(object, "y"),
+                            width: optional_attribute(object, "width"),
+                            height: optional_attribute(object, "height"),
+                            rotation: optional_attribute(object, "rotation"),
+                            visible: object.attribute("visible") != Some("0"),
+                            properties: properties(object),
+                        })
+                        .collect();
+
+                    map.layers.push(Layer::ObjectGroup(ObjectGroup {
+                        id: optional_attribute(node, "id"),
+                        name: node.attribute("name").unwrap_or_default().to_string(),
+                        x: 0,
+                        y: 0,
+                        opacity: 1.0,
+                        visible: node.attribute("visible") != Some("0"),
+                        locked: node.attribute("locked") == Some("1"),
+                        draworder: node.attribute("draworder").unwrap_or("topdown").to_string(),
+                        objects,
+                    }));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(map)
+    }
+
+    pub fn write_tmx(&self, writer: &mut impl Write) -> Result<(), Wolf3dError> {
+        writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            writer,
+            r#"<map version="{}" orientation="{}" renderorder="{}" width="{}" height="{}" tilewidth="{}" tileheight="{}" infinite="{}" nextlayerid="{}" nextobjectid="{}">"#,
+            escape(&self.version),
+            escape(&self.orientation),
+            escape(&self.renderorder),
+            self.width,
+            self.height,
+            self.tilewidth,
+            self.tileheight,
+            self.infinite as u8,
+            self.nextlayerid,
+            self.nextobjectid
+        )?;
+        write_properties(writer, &self.properties, " ")?;
+
+        for tileset in &self.tilesets {
+            writeln!(
+                writer,
+                r#" <tileset firstgid="{}" name="{}" tilewidth="{}" tileheight="{}" tilecount="{}" columns="{}">"#,
+                tileset.firstgid,
+                escape(&tileset.name),
+                tileset.tilewidth,
+                tileset.tileheight,
+                tileset.tilecount,
+                tileset.columns
+            )?;
+            writeln!(
+                writer,
+                r#"  <image source="{}" width="{}" height="{}"/>"#,
+                escape(&tileset.image),
+                tileset.imagewidth,
+                tileset.imageheight
+            )?;
+            writeln!(writer, " </tileset>")?;
+        }
+
+        for layer in &self.layers {
+            match layer {
+                Layer::TileLayer(layer) => {
+                    writeln!(
+                        writer,
+                        r#" <layer id="{}" name="{}" width="{}" height="{}">"#,
+                        layer.id,
+                        escape(&layer.name),
+                        layer.width,
+                        layer.height
+                    )?;
+                    writeln!(writer, r#"  <data encoding="csv">"#)?;
+                    let rows: Vec<String> = layer
+                        .data
+                        .chunks(layer.width.max(1))
+                        .map(|row| {
+                            row.iter()
+                                .map(|x| x.to_string())
+                                .collect::<Vec<_>>()
+                                .join(",")
+                        })
+                        .collect();
+                    writeln!(writer, "{}", rows.join(",\n"))?;
+                    writeln!(writer, "</data>")?;
+                    writeln!(writer, " </layer>")?;
+                }
+                Layer::ObjectGroup(group) => {
+                    writeln!(
+                        writer,
+                        r#" <objectgroup id="{}" name="{}" locked="{}">"#,
+                        group.id,
+                        escape(&group.name),
+                        group.locked as u8
+                    )?;
+                    for object in &group.objects {
+                        writeln!(
+                            writer,
+                            r#"  <object id="{}" name="{}" type="{}" x="{}" y="{}" width="{}" height="{}">"#,
+                            object.id,
+                            escape(&object.name),
+                            escape(&object.class),
+                            object.x,
+                            object.y,
+                            object.width,
+                            object.height
+                        )?;
+                        write_properties(writer, &object.properties, "   ")?;
+                        writeln!(writer, "  </object>")?;
+                    }
+                    writeln!(writer, " </objectgroup>")?;
+                }
+                Layer::Other => {}
+            }
+        }
+
+        writeln!(writer, "</map>")?;
+        Ok(())
+    }
+}
+
+// The image the exported tileset refers to, with every word coloured as a plane 0 tile.
+pub fn tileset_image() -> Image {
+    let size = TILE_SIZE as usize;
+    let columns = TILESET_COLUMNS as usize;
+    let colors: Vec<[u8; 3]> = (0..=u16::MAX).map(tile_color).collect();
+    let width = columns * size;
+    let height = TILE_COUNT as usize / columns * size;
+
+    let pixels = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| colors[y / size * columns + x / size])
+        .collect();
+
+    Image {
+        width,
+        height,
+        pixels,
+    }
+}
+
+// Name, class and the decoded properties of an object layer entry.
+fn describe(object: MapObject) -> (String, &'static str, Vec<Property>) {
+    let string = |name: &str, value: String| Property::new(name, "string", value);
+
+    match object {
+        MapObject::PlayerStart(facing) => (
+            "PlayerStart".to_string(),
+            "player_start",
+            vec![string("facing", format!("{:?}", facing))],
+        ),
+        MapObject::Static(object) => (
+            format!("{:?}", object),
+            "static",
+            vec![Property::new("treasure", "bool", object.is_treasure())],
+        ),
+        MapObject::TurnArrow(direction) => (
+            "TurnArrow".to_string(),
+            "turn_arrow",
+            vec![string("direction", format!("{:?}", direction))],
+        ),
+        MapObject::Pushwall => ("Pushwall".to_string(), "pushwall", vec![]),
+        MapObject::VictoryTrigger => ("VictoryTrigger".to_string(), "victory_trigger", vec![]),
+        MapObject::DeadGuard => ("DeadGuard".to_string(), "dead_guard", vec![]),
+        MapObject::Enemy(enemy) => (
+            format!("{:?}", enemy.kind),
+            "enemy",
+            vec![
+                string("difficulty", format!("{:?}", enemy.difficulty)),
+                string("facing", format!("{:?}", enemy.facing)),
+                Property::new("patrol", "bool", enemy.patrol),
+            ],
+        ),
+        MapObject::Boss(boss) => (format!("{:?}", boss), "boss", vec![]),
+    }
+}
+
+fn attribute<T: FromStr>(node: roxmltree::Node, name: &str) -> Result<T, Wolf3dError> {
+    node.attribute(name)
+        .and_then(|x| x.parse().ok())
+        .ok_or_else(|| {
+            Wolf3dError::Tiled(format!(
+                "<{}> has no valid {} attribute",
+                node.tag_name().name(),
+                name
+            ))
+        })
+}
+
+fn optional_attribute<T: FromStr + Default>(node: roxmltree::Node, name: &str) -> T {
+    node.attribute(name)
+        .and_then(|x| x.parse().ok())
+        .unwrap_or_default()
+}
+
+fn properties(node: roxmltree::Node) -> Vec<Property> {
+    node.children()
+        .filter(|x| x.has_tag_name("properties"))
+        .flat_map(|x| x.children().filter(|x| x.has_tag_name("property")))
+        .map(|property| {
+            let kind = property.attribute("type").unwrap_or("string");
+            let text = property
+                .attribute("value")
+                .or(property.text())
+                .unwrap_or_default();
+            let value = match kind {
+                "int" => text.parse::<i64>().map_or(Value::Null, Value::from),
+                "float" => text.parse::<f64>().map_or(Value::Null, Value::from),
+                "bool" => Value::Bool(text == "true"),
+                _ => Value::from(text),
+            };
+            Property::new(property.attribute("name").unwrap_or_default(), kind, value)
+        })
+        .collect()
+}
+
+fn write_properties(
+    writer: &mut impl Write,
+    properties: &[Property],
+    indent: &str,
+) -> Result<(), Wolf3dError> {
+    if properties.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(writer, "{}<properties>", indent)?;
+    for property in properties {
+        let value = match &property.value {
+            Value::String(x) => x.clone(),
+            x => x.to_string(),
+        };
+        if property.kind == "string" {
+            writeln!(
+                writer,
+                r#"{} <property name="{}" value="{}"/>"#,
+                indent,
+                escape(&property.name),
+                escape(&value)
+            )?;
+        } else {
+            writeln!(
+                writer,
+                r#"{} <property name="{}" type="{}" value="{}"/>"#,
+                indent,
+                escape(&property.name),
+                escape(&property.kind),
+                escape(&value)
+            )?;
+        }
+    }
+    writeln!(writer, "{}</properties>", indent)?;
+    Ok(())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_map() -> MapData {
+        let mut map = MapData::test_map("Wolf1 \"Map1\" <&>", 16, 8, 0);
+        // A hard patrolling guard facing south, and a player start facing north.
+        map.plane1.set(2, 3, 187);
+        map.plane1.set(4, 4, 19);
+        map.plane2.set(1, 1, 7);
+        map
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let map = test_map();
+        let mut json = vec![];
+
+        TiledMap::from_map(&map, Variant::Wolf3d)
+            .write_json(&mut json)
+            .unwrap();
+        let tiled = TiledMap::from_json(std::str::from_utf8(&json).unwrap()).unwrap();
+
+        assert_eq!(tiled.to_map().unwrap(), map);
+        assert_eq!(tiled.layers.len(), 4);
+    }
+
+    #[test]
+    fn test_large_map_round_trip() {
+        let map = MapData::test_map("Hangar", 300, 70, 2);
+        let mut json = vec![];
+
+        TiledMap::from_map(&map, Variant::Wolf3d)
+            .write_json(&mut json)
+            .unwrap();
+        let tiled = TiledMap::from_json(std::str::from_utf8(&json).unwrap()).unwrap();
+
+        assert_eq!(tiled.to_map().unwrap(), map);
+    }
+
+    #[test]
+    fn test_tmx_round_trip() {
+        let map = test_map();
+        let tiled = TiledMap::from_map(&map, Variant::Wolf3d);
+        let mut tmx = vec![];
+
+        tiled.write_tmx(&mut tmx).unwrap();
+        let parsed = TiledMap::from_tmx(std::str::from_utf8(&tmx).unwrap()).unwrap();
+
+        assert_eq!(parsed.to_map().unwrap(), map);
+        assert_eq!(parsed.layers, tiled.layers);
+    }
+
+    #[test]
+    fn test_object_layer_describes_plane1() {
+        let tiled = TiledMap::from_map(&test_map(), Variant::Wolf3d);
+        let Some(Layer::ObjectGroup(group)) = tiled.layers.last() else {
+            panic!("missing object layer");
+        };
+        let guard = group.objects.iter().find(|x| x.class == "enemy").unwrap();
+        let start = group
+            .objects
+            .iter()
+            .find(|x| x.class == "player_start")
+            .unwrap();
+
+        assert!(!group.locked);
+        assert_eq!(
+            (guard.name.as_str(), guard.x, guard.y),
+            ("Guard", 16.0, 24.0)
+        );
+        assert_eq!(
+            guard.properties,
+            vec![
+                Property::new("value", "int", 187),
+                Property::new("difficulty", "string", "Hard"),
+                Property::new("facing", "string", "South"),
+                Property::new("patrol", "bool", true),
+            ]
+        );
+        assert_eq!(
+            start.properties[1],
+            Property::new("facing", "string", "North")
+        );
+        assert_eq!(tiled.nextobjectid as usize, group.objects.len() + 1);
+    }
+
+    fn objects(tiled: &mut TiledMap) -> &mut Vec<Object> {
+        match tiled.layers.last_mut() {
+            Some(Layer::ObjectGroup(group)) => &mut group.objects,
+            _ => panic!("missing object layer"),
+        }
+    }
+
+    #[test]
+    fn test_object_edits_reach_plane1() {
+        let mut tiled = TiledMap::from_map(&test_map(), Variant::Wolf3d);
+        let guard = objects(&mut tiled)
+            .iter_mut()
+            .find(|x| x.class == "enemy")
+            .unwrap();
+        guard.x = 5.0 * 8.0;
+        guard.y = 6.0 * 8.0 + 3.0;
+        let start = objects(&mut tiled)
+            .iter_mut()
+            .find(|x| x.class == "player_start")
+            .unwrap();
+        start.properties = vec![
+            Property::new("value", "int", 21),
+            Property::new("facing", "string", "South"),
+        ];
+
+        let map = tiled.to_map().unwrap();
+
+        assert_eq!(map.plane1.get(2, 3), Some(0));
+        assert_eq!(map.plane1.get(5, 6), Some(187));
+        assert_eq!(map.plane1.get(4, 4), Some(21));
+    }
+
+    #[test]
+    fn test_object_layer_conflicts() {
+        let tiled = TiledMap::from_map(&test_map(), Variant::Wolf3d);
+        let message = |tiled: &TiledMap| match tiled.to_map() {
+            Err(Wolf3dError::Tiled(message)) => message,
+            x => panic!("expected an error, got {:?}", x),
+        };
+
+        let mut covered = tiled.clone();
+        let Layer::TileLayer(layer) = &mut covered.layers[1] else {
+            panic!("missing plane1");
+        };
+        layer.data[3 * 16 + 2] = 24 + FIRST_GID;
+
+        let mut stacked = tiled.clone();
+        let (x, y) = (objects(&mut stacked)[0].x, objects(&mut stacked)[0].y);
+        objects(&mut stacked)[1].x = x;
+        objects(&mut stacked)[1].y = y;
+
+        let mut renamed = tiled.clone();
+        let guard = objects(&mut renamed)
+            .iter_mut()
+            .find(|x| x.class == "enemy")
+            .unwrap();
+        guard.properties[1] = Property::new("difficulty", "string", "Easy");
+
+        let mut outside = tiled.clone();
+        objects(&mut outside)[0].x = -1.0;
+
+        assert_eq!(
+            message(&covered),
+            "object 5 (Guard): plane1 holds 24 at (2,3), not 187"
+        );
+        assert!(message(&stacked).contains("another object is at"));
+        assert!(message(&renamed).contains("difficulty doesn't match value 187"));
+        assert!(message(&outside).ends_with("outside the map"));
+    }
+
+    #[test]
+    fn test_import_empty_and_flipped_tiles() {
+        let json = r#"{
+            "width": 2, "height": 1,
+            "tilesets": [{"firstgid": 1, "source": "wolf3d.tsj"}],
+            "layers": [
+                {"type": "tilelayer", "name": "plane0", "width": 2, "height": 1, "data": [0, 2147483650]},
+                {"type": "tilelayer", "name": "plane1", "width": 2, "height": 1, "data": [1, 24]},
+                {"type": "group", "name": "extra"}
+            ]
+        }"#;
+
+        let map = TiledMap::from_json(json).unwrap().to_map().unwrap();
+
+        assert_eq!(map.plane0.cells(), &[0, 1]);
+        assert_eq!(map.plane1.cells(), &[0, 23]);
+        assert_eq!(map.plane2.cells(), &[0, 0]);
+        assert_eq!(map.name, "");
+    }
+
+    #[test]
+    fn test_import_errors() {
+        let mut tiled = TiledMap::from_map(&test_map(), Variant::Wolf3d);
+        let Layer::TileLayer(layer) = &mut tiled.layers[1] else {
+            panic!("missing plane1");
+        };
+        layer.data.pop();
+
+        assert!(matches!(tiled.to_map(), Err(Wolf3dError::Tiled(_))));
+
+        tiled.layers.remove(0);
+        let Err(Wolf3dError::Tiled(message)) = tiled.to_map() else {
+            panic!("expected an error");
+        };
+        assert_eq!(message, "missing layer plane0");
+        assert!(TiledMap::from_tmx("<map/>").is_err());
+    }
+
+    #[test]
+    fn test_tileset_image() {
+        let image = tileset_image();
+
+        assert_eq!((image.width, image.height), (2048, 2048));
+        assert_eq!(image.pixels[8 * 2048 * 8 + 8 * 3], tile_color(256 + 3));
+    }
+}
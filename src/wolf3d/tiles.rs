@@ -0,0 +1,756 @@
+use super::Variant;
+
+// Plane 0 holds walls, doors and floor area codes; plane 1 holds actors and objects. The values
+// follow SetupGameLevel and ScanInfoPlane in the original engine.
+
+const FIRST_DOOR: u16 = 90;
+const LAST_DOOR: u16 = 101;
+const FIRST_SOLID: u16 = LAST_DOOR + 1;
+const LAST_SOLID: u16 = AMBUSH_TILE - 1;
+const AMBUSH_TILE: u16 = 106;
+const AREA_TILE: u16 = 107;
+const NUM_AREAS: u16 = 37;
+const LAST_AREA: u16 = AREA_TILE + NUM_AREAS - 1;
+const ELEVATOR_TILE: u16 = 21;
+
+const PLAYER_START: u16 = 19;
+const FIRST_STATIC: u16 = 23;
+const ICON_ARROWS: u16 = 90;
+const PUSHABLE_TILE: u16 = 98;
+const EXIT_TILE: u16 = 99;
+const DEAD_GUARD: u16 = 124;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WallTile(u8);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DoorLock {
+    Normal,
+    GoldKey,
+    SilverKey,
+    Lock3,
+    Lock4,
+    Elevator,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DoorTile {
+    pub vertical: bool,
+    pub lock: DoorLock,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AreaTile(u8);
+
+// Enemies standing on an ambush tile don't react to sounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AmbushTile;
+
+// The switch is a wall; pressing it while standing on the first area code leads to the secret
+// level instead of the next one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ElevatorTile {
+    Switch,
+    SecretFloor,
+}
+
+// Values between the doors and the ambush tile have no texture, but the engine still blocks
+// movement and sight through anything below the area codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SolidTile(u8);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tile {
+    Empty,
+    Wall(WallTile),
+    Elevator(ElevatorTile),
+    Door(DoorTile),
+    Solid(SolidTile),
+    Ambush(AmbushTile),
+    Area(AreaTile),
+}
+
+impl WallTile {
+    pub fn from_u16(value: u16) -> Option<Self> {
+        match value {
+            1..FIRST_DOOR => Some(Self(value as u8)),
+            _ => None,
+        }
+    }
+
+    pub fn to_u16(self) -> u16 {
+        self.0 as u16
+    }
+
+    // Every wall has a light texture for north/south faces and a dark one for east/west faces.
+    pub fn light_texture(self) -> usize {
+        (self.0 as usize - 1) * 2
+    }
+
+    pub fn dark_texture(self) -> usize {
+        self.light_texture() + 1
+    }
+}
+
+impl DoorLock {
+    const ALL: [DoorLock; 6] = [
+        DoorLock::Normal,
+        DoorLock::GoldKey,
+        DoorLock::SilverKey,
+        DoorLock::Lock3,
+        DoorLock::Lock4,
+        DoorLock::Elevator,
+    ];
+}
+
+impl DoorTile {
+    pub fn from_u16(value: u16) -> Option<Self> {
+        if !(FIRST_DOOR..=LAST_DOOR).contains(&value) {
+            return None;
+        }
+
+        Some(Self {
+            vertical: (value - FIRST_DOOR).is_multiple_of(2),
+            lock: DoorLock::ALL[(value - FIRST_DOOR) as usize / 2],
+        })
+    }
+
+    pub fn to_u16(self) -> u16 {
+        let lock = DoorLock::ALL
+            .iter()
+            .position(|&x| x == self.lock)
+            .unwrap_or(0) as u16;
+        FIRST_DOOR + lock * 2 + if self.vertical { 0 } else { 1 }
+    }
+}
+
+impl SolidTile {
+    pub fn from_u16(value: u16) -> Option<Self> {
+        match value {
+            FIRST_SOLID..=LAST_SOLID => Some(Self(value as u8)),
+            _ => None,
+        }
+    }
+
+    pub fn to_u16(self) -> u16 {
+        self.0 as u16
+    }
+}
+
+impl AreaTile {
+    pub fn from_u16(value: u16) -> Option<Self> {
+        match value {
+            AREA_TILE..=LAST_AREA => Some(Self((value - AREA_TILE) as u8)),
+            _ => None,
+        }
+    }
+
+    pub fn to_u16(self) -> u16 {
+        AREA_TILE + self.0 as u16
+    }
+
+    pub fn new(number: u8) -> Option<Self> {
+        ((number as u16) < NUM_AREAS).then_some(Self(number))
+    }
+
+    pub fn number(self) -> u8 {
+        self.0
+    }
+}
+
+impl AmbushTile {
+    pub fn from_u16(value: u16) -> Option<Self> {
+        (value == AMBUSH_TILE).then_some(Self)
+    }
+
+    pub fn to_u16(self) -> u16 {
+        AMBUSH_TILE
+    }
+}
+
+impl ElevatorTile {
+    pub fn from_u16(value: u16) -> Option<Self> {
+        match value {
+            ELEVATOR_TILE => Some(Self::Switch),
+            AREA_TILE => Some(Self::SecretFloor),
+            _ => None,
+        }
+    }
+
+    pub fn to_u16(self) -> u16 {
+        match self {
+            Self::Switch => ELEVATOR_TILE,
+            Self::SecretFloor => AREA_TILE,
+        }
+    }
+}
+
+impl Tile {
+    // The secret elevator floor is also area 0, so it decodes as an area tile.
+    pub fn from_u16(value: u16) -> Option<Self> {
+        match value {
+            0 => Some(Self::Empty),
+            ELEVATOR_TILE => Some(Self::Elevator(ElevatorTile::Switch)),
+            _ => WallTile::from_u16(value)
+                .map(Self::Wall)
+                .or_else(|| DoorTile::from_u16(value).map(Self::Door))
+                .or_else(|| SolidTile::from_u16(value).map(Self::Solid))
+                .or_else(|| AmbushTile::from_u16(value).map(Self::Ambush))
+                .or_else(|| AreaTile::from_u16(value).map(Self::Area)),
+        }
+    }
+
+    pub fn to_u16(self) -> u16 {
+        match self {
+            Self::Empty => 0,
+            Self::Wall(x) => x.to_u16(),
+            Self::Elevator(x) => x.to_u16(),
+            Self::Door(x) => x.to_u16(),
+            Self::Solid(x) => x.to_u16(),
+            Self::Ambush(x) => x.to_u16(),
+            Self::Area(x) => x.to_u16(),
+        }
+    }
+
+    pub fn is_floor(self) -> bool {
+        matches!(self, Self::Ambush(_) | Self::Area(_))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Facing {
+    North,
+    East,
+    South,
+    West,
+}
+
+// Patrolling enemies turn when they step on an arrow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction8 {
+    East,
+    NorthEast,
+    North,
+    NorthWest,
+    West,
+    SouthWest,
+    South,
+    SouthEast,
+}
+
+// Enemies placed for a difficulty also appear on every harder one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EnemyKind {
+    Guard,
+    Officer,
+    SS,
+    Dog,
+    Mutant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Enemy {
+    pub kind: EnemyKind,
+    pub difficulty: Difficulty,
+    pub facing: Facing,
+    pub patrol: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Boss {
+    Hans,
+    Schabbs,
+    FakeHitler,
+    Hitler,
+    Fat,
+    Gretel,
+    Gift,
+    Blinky,
+    Clyde,
+    Pinky,
+    Inky,
+    Spectre,
+    Angel,
+    TransGrosse,
+    UberMutant,
+    Wilhelm,
+    DeathKnight,
+}
+
+// Named after the comments in the engine's statinfo table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StaticObject {
+    Puddle,
+    GreenBarrel,
+    TableWithChairs,
+    FloorLamp,
+    Chandelier,
+    HangedMan,
+    BadFood,
+    RedPillar,
+    Tree,
+    SkeletonFlat,
+    Sink,
+    PottedPlant,
+    Urn,
+    BareTable,
+    CeilingLight,
+    KitchenStuff,
+    SuitOfArmor,
+    HangingCage,
+    SkeletonInCage,
+    SkeletonRelaxed,
+    GoldKey,
+    SilverKey,
+    Bed,
+    Basket,
+    Food,
+    FirstAid,
+    Clip,
+    MachineGun,
+    ChainGun,
+    Cross,
+    Chalice,
+    Bible,
+    Crown,
+    ExtraLife,
+    Gibs,
+    Barrel,
+    Well,
+    EmptyWell,
+    Gibs2,
+    Flag,
+    CallApogee,
+    Junk1,
+    Junk2,
+    Junk3,
+    Pots,
+    Stove,
+    Spears,
+    Vines,
+    MarblePillar,
+    Clip25,
+    Truck,
+    SpearOfDestiny,
+    DroppedClip,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MapObject {
+    PlayerStart(Facing),
+    Static(StaticObject),
+    TurnArrow(Direction8),
+    Pushwall,
+    VictoryTrigger,
+    DeadGuard,
+    Enemy(Enemy),
+    Boss(Boss),
+}
+
+const FACINGS: [Facing; 4] = [Facing::North, Facing::East, Facing::South, Facing::West];
+
+// Enemy directions count anticlockwise from east.
+const ENEMY_FACINGS: [Facing; 4] = [Facing::East, Facing::North, Facing::West, Facing::South];
+
+const DIRECTIONS: [Direction8; 8] = [
+    Direction8::East,
+    Direction8::NorthEast,
+    Direction8::North,
+    Direction8::NorthWest,
+    Direction8::West,
+    Direction8::SouthWest,
+    Direction8::South,
+    Direction8::SouthEast,
+];
+
+const DIFFICULTIES: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+
+// (kind, standing on easy, step to the next difficulty)
+const ENEMIES: [(EnemyKind, u16, u16); 5] = [
+    (EnemyKind::Guard, 108, 36),
+    (EnemyKind::Officer, 116, 36),
+    (EnemyKind::SS, 126, 36),
+    (EnemyKind::Dog, 134, 36),
+    (EnemyKind::Mutant, 216, 18),
+];
+
+const WOLF3D_BOSSES: [(Boss, u16); 11] = [
+    (Boss::FakeHitler, 160),
+    (Boss::Hitler, 178),
+    (Boss::Fat, 179),
+    (Boss::Schabbs, 196),
+    (Boss::Gretel, 197),
+    (Boss::Hans, 214),
+    (Boss::Gift, 215),
+    (Boss::Blinky, 224),
+    (Boss::Clyde, 225),
+    (Boss::Pinky, 226),
+    (Boss::Inky, 227),
+];
+
+const SPEAR_BOSSES: [(Boss, u16); 6] = [
+    (Boss::Spectre, 106),
+    (Boss::Angel, 107),
+    (Boss::TransGrosse, 125),
+    (Boss::UberMutant, 142),
+    (Boss::Wilhelm, 143),
+    (Boss::DeathKnight, 161),
+];
+
+impl StaticObject {
+    // Spear of Destiny inserts four objects before the dropped clip.
+    const ALL: [StaticObject; 53] = [
+        StaticObject::Puddle,
+        StaticObject::GreenBarrel,
+        StaticObject::TableWithChairs,
+        StaticObject::FloorLamp,
+        StaticObject::Chandelier,
+        StaticObject::HangedMan,
+        StaticObject::BadFood,
+        StaticObject::RedPillar,
+        StaticObject::Tree,
+        StaticObject::SkeletonFlat,
+        StaticObject::Sink,
+        StaticObject::PottedPlant,
+        StaticObject::Urn,
+        StaticObject::BareTable,
+        StaticObject::CeilingLight,
+        StaticObject::KitchenStuff,
+        StaticObject::SuitOfArmor,
+        StaticObject::HangingCage,
+        StaticObject::SkeletonInCage,
+        StaticObject::SkeletonRelaxed,
+        StaticObject::GoldKey,
+        StaticObject::SilverKey,
+        StaticObject::Bed,
+        StaticObject::Basket,
+        StaticObject::Food,
+        StaticObject::FirstAid,
+        StaticObject::Clip,
+        StaticObject::MachineGun,
+        StaticObject::ChainGun,
+        StaticObject::Cross,
+        StaticObject::Chalice,
+        StaticObject::Bible,
+        StaticObject::Crown,
+        StaticObject::ExtraLife,
+        StaticObject::Gibs,
+        StaticObject::Barrel,
+        StaticObject::Well,
+        StaticObject::EmptyWell,
+        StaticObject::Gibs2,
+        StaticObject::Flag,
+        StaticObject::CallApogee,
+        StaticObject::Junk1,
+        StaticObject::Junk2,
+        StaticObject::Junk3,
+        StaticObject::Pots,
+        StaticObject::Stove,
+        StaticObject::Spears,
+        StaticObject::Vines,
+        StaticObject::MarblePillar,
+        StaticObject::Clip25,
+        StaticObject::Truck,
+        StaticObject::SpearOfDestiny,
+        StaticObject::DroppedClip,
+    ];
+
+    // The statics ScanInfoPlane spawns from map values; the dropped clip only appears at runtime.
+    fn table(variant: Variant) -> &'static [StaticObject] {
+        if is_spear(variant) {
+            &Self::ALL[..52]
+        } else {
+            &Self::ALL[..48]
+        }
+    }
+
+    pub fn from_index(index: usize, variant: Variant) -> Option<Self> {
+        match index {
+            48 if !is_spear(variant) => Some(Self::DroppedClip),
+            _ => Self::table(variant).get(index).copied(),
+        }
+    }
+
+    pub fn index(self, variant: Variant) -> Option<usize> {
+        if self == Self::DroppedClip && !is_spear(variant) {
+            return Some(48);
+        }
+        Self::table(variant).iter().position(|&x| x == self)
+    }
+
+    pub fn is_treasure(self) -> bool {
+        matches!(
+            self,
+            Self::Cross | Self::Chalice | Self::Bible | Self::Crown
+        )
+    }
+}
+
+impl MapObject {
+    // Super 3-D Noah's Ark numbers its objects differently and decodes to nothing.
+    pub fn from_u16(value: u16, variant: Variant) -> Option<Self> {
+        if variant == Variant::NoahsArk {
+            return None;
+        }
+
+        match value {
+            19..=22 => return Some(Self::PlayerStart(FACINGS[(value - PLAYER_START) as usize])),
+            90..=97 => return Some(Self::TurnArrow(DIRECTIONS[(value - ICON_ARROWS) as usize])),
+            PUSHABLE_TILE => return Some(Self::Pushwall),
+            EXIT_TILE => return Some(Self::VictoryTrigger),
+            DEAD_GUARD => return Some(Self::DeadGuard),
+            FIRST_STATIC.. if value < ICON_ARROWS => {
+                return StaticObject::from_index((value - FIRST_STATIC) as usize, variant)
+                    .map(Self::Static);
+            }
+            _ => {}
+        }
+
+        let bosses: &[(Boss, u16)] = if is_spear(variant) {
+            &SPEAR_BOSSES
+        } else {
+            &WOLF3D_BOSSES
+        };
+        if let Some(&(boss, _)) = bosses.iter().find(|&&(_, x)| x == value) {
+            return Some(Self::Boss(boss));
+        }
+
+        ENEMIES.iter().find_map(|&(kind, first, step)| {
+            DIFFICULTIES
+                .iter()
+                .enumerate()
+                .find_map(|(i, &difficulty)| {
+                    let offset = value.checked_sub(first + step * i as u16)?;
+                    (offset < 8).then_some(Self::Enemy(Enemy {
+                        kind,
+                        difficulty,
+                        facing: ENEMY_FACINGS[offset as usize % 4],
+                        patrol: offset >= 4,
+                    }))
+                })
+        })
+    }
+
+    // Returns None for objects the variant doesn't have.
+    pub fn to_u16(self, variant: Variant) -> Option<u16> {
+        let value = match self {
+            Self::PlayerStart(facing) => PLAYER_START + position(&FACINGS, facing),
+            Self::Static(object) => FIRST_STATIC + object.index(variant)? as u16,
+            Self::TurnArrow(direction) => ICON_ARROWS + position(&DIRECTIONS, direction),
+            Self::Pushwall => PUSHABLE_TILE,
+            Self::VictoryTrigger => EXIT_TILE,
+            Self::DeadGuard => DEAD_GUARD,
+            Self::Enemy(enemy) => {
+                let &(_, first, step) = ENEMIES.iter().find(|x| x.0 == enemy.kind)?;
+                first
+                    + step * position(&DIFFICULTIES, enemy.difficulty)
+                    + if enemy.patrol { 4 } else { 0 }
+                    + position(&ENEMY_FACINGS, enemy.facing)
+            }
+            Self::Boss(boss) => {
+                let bosses: &[(Boss, u16)] = if is_spear(variant) {
+                    &SPEAR_BOSSES
+                } else {
+                    &WOLF3D_BOSSES
+                };
+                bosses.iter().find(|x| x.0 == boss)?.1
+            }
+        };
+
+        (Self::from_u16(value, variant) == Some(self)).then_some(value)
+    }
+}
+
+fn is_spear(variant: Variant) -> bool {
+    matches!(
+        variant,
+        Variant::SpearOfDestiny | Variant::SpearOfDestinyDemo
+    )
+}
+
+fn position<T: PartialEq>(values: &[T], value: T) -> u16 {
+    values.iter().position(|x| *x == value).unwrap_or(0) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tile_round_trip_all_values() {
+        let mut counts = [0; 7];
+        for value in 0..=u16::MAX {
+            let Some(tile) = Tile::from_u16(value) else {
+                continue;
+            };
+            assert_eq!(tile.to_u16(), value);
+            counts[match tile {
+                Tile::Empty => 0,
+                Tile::Wall(_) => 1,
+                Tile::Elevator(_) => 2,
+                Tile::Door(_) => 3,
+                Tile::Solid(_) => 4,
+                Tile::Ambush(_) => 5,
+                Tile::Area(_) => 6,
+            }] += 1;
+        }
+
+        assert_eq!(counts, [1, 88, 1, 12, 4, 1, 37]);
+    }
+
+    #[test]
+    fn test_plane0_types_round_trip_all_values() {
+        for value in 0..=u16::MAX {
+            if let Some(x) = WallTile::from_u16(value) {
+                assert_eq!(x.to_u16(), value);
+            }
+            if let Some(x) = DoorTile::from_u16(value) {
+                assert_eq!(x.to_u16(), value);
+            }
+            if let Some(x) = AreaTile::from_u16(value) {
+                assert_eq!(x.to_u16(), value);
+                assert_eq!(AreaTile::new(x.number()), Some(x));
+            }
+            if let Some(x) = SolidTile::from_u16(value) {
+                assert_eq!(x.to_u16(), value);
+            }
+            if let Some(x) = AmbushTile::from_u16(value) {
+                assert_eq!(x.to_u16(), value);
+            }
+            if let Some(x) = ElevatorTile::from_u16(value) {
+                assert_eq!(x.to_u16(), value);
+            }
+        }
+        assert_eq!(AreaTile::new(37), None);
+    }
+
+    #[test]
+    fn test_plane0_values() {
+        assert_eq!(
+            Tile::from_u16(90),
+            Some(Tile::Door(DoorTile {
+                vertical: true,
+                lock: DoorLock::Normal
+            }))
+        );
+        assert_eq!(
+            DoorTile::from_u16(95),
+            Some(DoorTile {
+                vertical: false,
+                lock: DoorLock::SilverKey
+            })
+        );
+        assert_eq!(DoorTile::from_u16(100).unwrap().lock, DoorLock::Elevator);
+        assert_eq!(
+            Tile::from_u16(21),
+            Some(Tile::Elevator(ElevatorTile::Switch))
+        );
+        assert_eq!(ElevatorTile::from_u16(107), Some(ElevatorTile::SecretFloor));
+        assert_eq!(Tile::from_u16(107), AreaTile::new(0).map(Tile::Area));
+        assert!(Tile::from_u16(106).unwrap().is_floor());
+        assert_eq!(Tile::from_u16(102), Some(Tile::Solid(SolidTile(102))));
+        assert_eq!(Tile::from_u16(105).map(Tile::to_u16), Some(105));
+        assert!(!Tile::from_u16(105).unwrap().is_floor());
+        assert_eq!(Tile::from_u16(144), None);
+
+        let wall = WallTile::from_u16(3).unwrap();
+        assert_eq!((wall.light_texture(), wall.dark_texture()), (4, 5));
+    }
+
+    #[test]
+    fn test_map_object_round_trip_all_values() {
+        for (variant, expected) in [
+            (Variant::Wolf3d, 195),
+            (Variant::Wolf3dShareware, 195),
+            (Variant::SpearOfDestiny, 193),
+            (Variant::SpearOfDestinyDemo, 193),
+            (Variant::NoahsArk, 0),
+        ] {
+            let mut count = 0;
+            for value in 0..=u16::MAX {
+                if let Some(object) = MapObject::from_u16(value, variant) {
+                    assert_eq!(object.to_u16(variant), Some(value), "{:?}", object);
+                    count += 1;
+                }
+            }
+            assert_eq!(count, expected, "{:?}", variant);
+        }
+    }
+
+    #[test]
+    fn test_map_object_values() {
+        let wolf3d = |x| MapObject::from_u16(x, Variant::Wolf3d);
+        let spear = |x| MapObject::from_u16(x, Variant::SpearOfDestiny);
+
+        assert_eq!(wolf3d(0), None);
+        assert_eq!(wolf3d(19), Some(MapObject::PlayerStart(Facing::North)));
+        assert_eq!(wolf3d(22), Some(MapObject::PlayerStart(Facing::West)));
+        assert_eq!(wolf3d(29), Some(MapObject::Static(StaticObject::BadFood)));
+        assert_eq!(wolf3d(43), Some(MapObject::Static(StaticObject::GoldKey)));
+        assert_eq!(wolf3d(56), Some(MapObject::Static(StaticObject::ExtraLife)));
+        assert_eq!(
+            wolf3d(71),
+            Some(MapObject::Static(StaticObject::DroppedClip))
+        );
+        assert_eq!(wolf3d(72), None);
+        assert_eq!(
+            spear(71),
+            Some(MapObject::Static(StaticObject::MarblePillar))
+        );
+        assert_eq!(
+            spear(74),
+            Some(MapObject::Static(StaticObject::SpearOfDestiny))
+        );
+        assert_eq!(spear(75), None);
+        assert_eq!(wolf3d(90), Some(MapObject::TurnArrow(Direction8::East)));
+        assert_eq!(
+            wolf3d(97),
+            Some(MapObject::TurnArrow(Direction8::SouthEast))
+        );
+        assert_eq!(wolf3d(98), Some(MapObject::Pushwall));
+        assert_eq!(wolf3d(124), Some(MapObject::DeadGuard));
+        assert_eq!(
+            wolf3d(108),
+            Some(MapObject::Enemy(Enemy {
+                kind: EnemyKind::Guard,
+                difficulty: Difficulty::Easy,
+                facing: Facing::East,
+                patrol: false
+            }))
+        );
+        assert_eq!(
+            wolf3d(187),
+            Some(MapObject::Enemy(Enemy {
+                kind: EnemyKind::Guard,
+                difficulty: Difficulty::Hard,
+                facing: Facing::South,
+                patrol: true
+            }))
+        );
+        assert_eq!(
+            wolf3d(239),
+            Some(MapObject::Enemy(Enemy {
+                kind: EnemyKind::Mutant,
+                difficulty: Difficulty::Medium,
+                facing: Facing::North,
+                patrol: true
+            }))
+        );
+        assert_eq!(wolf3d(214), Some(MapObject::Boss(Boss::Hans)));
+        assert_eq!(wolf3d(227), Some(MapObject::Boss(Boss::Inky)));
+        assert_eq!(wolf3d(161), None);
+        assert_eq!(spear(161), Some(MapObject::Boss(Boss::DeathKnight)));
+        assert_eq!(spear(224), None);
+        assert_eq!(
+            MapObject::Static(StaticObject::Truck).to_u16(Variant::Wolf3d),
+            None
+        );
+    }
+}
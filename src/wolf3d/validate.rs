@@ -0,0 +1,335 @@
+use std::fmt;
+
+use super::tiles::{DoorLock, DoorTile, ElevatorTile, MapObject, StaticObject, Tile};
+use super::{Grid, MapData, Variant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Problem {
+    NoPlayerStart,
+    ExtraPlayerStart,
+    NoExit,
+    UnreachableExit,
+    KeyBehindOwnDoor(DoorLock),
+    MissingKey(DoorLock),
+    EnemyInWall,
+    DoorWithoutFrame,
+    ObjectOnBorder,
+    UnknownTile(u16),
+    UnknownObject(u16),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Finding {
+    pub position: Option<(usize, usize)>,
+    pub problem: Problem,
+}
+
+impl Problem {
+    // Errors stop a level from being played through; the engine copes with warnings.
+    pub fn severity(self) -> Severity {
+        match self {
+            Self::NoPlayerStart
+            | Self::UnreachableExit
+            | Self::KeyBehindOwnDoor(_)
+            | Self::MissingKey(_)
+            | Self::DoorWithoutFrame
+            | Self::UnknownTile(_) => Severity::Error,
+            Self::ExtraPlayerStart
+            | Self::NoExit
+            | Self::EnemyInWall
+            | Self::ObjectOnBorder
+            | Self::UnknownObject(_) => Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoPlayerStart => write!(f, "no player start"),
+            Self::ExtraPlayerStart => write!(f, "player start is overridden by a later one"),
+            Self::NoExit => write!(f, "no elevator or victory trigger"),
+            Self::UnreachableExit => write!(f, "exit can't be reached from the player start"),
+            Self::KeyBehindOwnDoor(lock) => {
+                write!(f, "{:?} key can only be reached through its own door", lock)
+            }
+            Self::MissingKey(lock) => write!(f, "{:?} door has no matching key", lock),
+            Self::EnemyInWall => write!(f, "enemy is not on a floor tile"),
+            Self::DoorWithoutFrame => write!(f, "door is not between two walls"),
+            Self::ObjectOnBorder => write!(f, "object on the map border"),
+            Self::UnknownTile(value) => write!(f, "unknown plane 0 value {}", value),
+            Self::UnknownObject(value) => write!(f, "unknown plane 1 value {}", value),
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.problem.severity() {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        match self.position {
+            Some((x, y)) => write!(f, "({},{}) {}: {}", x, y, severity, self.problem),
+            None => write!(f, "{}: {}", severity, self.problem),
+        }
+    }
+}
+
+// Checks a map for the mistakes that break custom levels. Noah's Ark objects aren't decoded, so
+// only its tiles are checked.
+pub fn validate(map: &MapData, variant: Variant) -> Vec<Finding> {
+    let mut findings = vec![];
+    let mut report =
+        |position: Option<(usize, usize)>, problem| findings.push(Finding { position, problem });
+    let tile = |x: usize, y: usize| map.plane0.get(x, y).and_then(Tile::from_u16);
+    let is_wall = |x: Option<usize>, y: Option<usize>| {
+        matches!(
+            x.zip(y).and_then(|(x, y)| tile(x, y)),
+            Some(Tile::Wall(_) | Tile::Solid(_) | Tile::Elevator(ElevatorTile::Switch))
+        )
+    };
+
+    for (x, y, value) in map.plane0.iter_with_coords() {
+        match Tile::from_u16(value) {
+            None => report(Some((x, y)), Problem::UnknownTile(value)),
+            Some(Tile::Door(door)) => {
+                let framed = if door.vertical {
+                    is_wall(Some(x), y.checked_sub(1)) && is_wall(Some(x), y.checked_add(1))
+                } else {
+                    is_wall(x.checked_sub(1), Some(y)) && is_wall(x.checked_add(1), Some(y))
+                };
+                if !framed {
+                    report(Some((x, y)), Problem::DoorWithoutFrame);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if variant == Variant::NoahsArk {
+        return findings;
+    }
+
+    let (width, height) = (map.plane1.width(), map.plane1.height());
+    for (x, y, value) in map.plane1.iter_with_coords() {
+        if value == 0 {
+            continue;
+        }
+        if x == 0 || y == 0 || x + 1 == width || y + 1 == height {
+            report(Some((x, y)), Problem::ObjectOnBorder);
+        }
+        match MapObject::from_u16(value, variant) {
+            None => report(Some((x, y)), Problem::UnknownObject(value)),
+            Some(MapObject::Enemy(_) | MapObject::Boss(_))
+                if !tile(x, y).is_some_and(|x| x.is_floor()) =>
+            {
+                report(Some((x, y)), Problem::EnemyInWall)
+            }
+            _ => {}
+        }
+    }
+
+    let starts = map.player_starts(variant);
+    let Some(&(start_x, start_y)) = starts.last() else {
+        report(None, Problem::NoPlayerStart);
+        return findings;
+    };
+    for &start in &starts[..starts.len() - 1] {
+        report(Some(start), Problem::ExtraPlayerStart);
+    }
+
+    let reachable_without = |lock: Option<DoorLock>| {
+        map.plane0.flood_fill(start_x, start_y, |x, y, value| {
+            let blocked = lock.is_some_and(|lock| {
+                DoorTile::from_u16(value).is_some_and(|door| door.lock == lock)
+            });
+            !blocked && map.is_passable(x, y, variant)
+        })
+    };
+    let reachable = reachable_without(None);
+
+    let exits = exits(map, variant);
+    if exits.is_empty() {
+        report(None, Problem::NoExit);
+    } else if !exits.iter().any(|&(x, y)| reaches(&reachable, map, x, y)) {
+        for &exit in &exits {
+            report(Some(exit), Problem::UnreachableExit);
+        }
+    }
+
+    for lock in [
+        DoorLock::GoldKey,
+        DoorLock::SilverKey,
+        DoorLock::Lock3,
+        DoorLock::Lock4,
+    ] {
+        let keys: Vec<(usize, usize)> = map
+            .plane1
+            .iter_with_coords()
+            .filter(|&(_, _, value)| {
+                let key = match MapObject::from_u16(value, variant) {
+                    Some(MapObject::Static(StaticObject::GoldKey)) => DoorLock::GoldKey,
+                    Some(MapObject::Static(StaticObject::SilverKey)) => DoorLock::SilverKey,
+                    _ => return false,
+                };
+                key == lock
+            })
+            .map(|(x, y, _)| (x, y))
+            .collect();
+
+        let doors = map
+            .plane0
+            .iter_with_coords()
+            .filter(|&(_, _, value)| DoorTile::from_u16(value).is_some_and(|x| x.lock == lock));
+        if keys.is_empty() {
+            for (x, y, _) in doors {
+                report(Some((x, y)), Problem::MissingKey(lock));
+            }
+            continue;
+        }
+
+        let restricted = reachable_without(Some(lock));
+        for (x, y) in keys {
+            if reachable.get(x, y) == Some(true) && restricted.get(x, y) != Some(true) {
+                report(Some((x, y)), Problem::KeyBehindOwnDoor(lock));
+            }
+        }
+    }
+
+    findings
+}
+
+// Elevator switches, elevator doors and victory triggers all end the level.
+fn exits(map: &MapData, variant: Variant) -> Vec<(usize, usize)> {
+    map.plane0
+        .iter_with_coords()
+        .filter(|&(x, y, value)| {
+            let exit_tile = match Tile::from_u16(value) {
+                Some(Tile::Elevator(ElevatorTile::Switch)) => true,
+                Some(Tile::Door(door)) => door.lock == DoorLock::Elevator,
+                _ => false,
+            };
+            exit_tile
+                || MapObject::from_u16(map.plane1.get(x, y).unwrap_or(0), variant)
+                    == Some(MapObject::VictoryTrigger)
+        })
+        .map(|(x, y, _)| (x, y))
+        .collect()
+}
+
+// Switches sit in walls, so they count as reached when the player can stand next to one.
+fn reaches(reachable: &Grid<bool>, map: &MapData, x: usize, y: usize) -> bool {
+    reachable.get(x, y) == Some(true)
+        || (map.plane0.get(x, y) == Some(ElevatorTile::Switch.to_u16())
+            && reachable.neighbors(x, y).any(|(_, _, x)| x))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wolf3d::Plane;
+
+    // A start room, a gold door to the elevator switch and a silver door to a closet.
+    #[rustfmt::skip]
+    const PLANE0: [u16; 42] = [
+        1, 1,   1,   1,  1,   1,  1,
+        1, 108, 108, 92, 109, 21, 1,
+        1, 108, 1,   1,  1,   1,  1,
+        1, 95,  1,   1,  1,   1,  1,
+        1, 110, 1,   1,  1,   1,  1,
+        1, 1,   1,   1,  1,   1,  1,
+    ];
+
+    fn test_map(plane0: &[u16], objects: &[((usize, usize), u16)]) -> MapData {
+        let mut plane1 = Plane::new(7, 6, 0);
+        for &((x, y), value) in objects {
+            plane1.set(x, y, value);
+        }
+        MapData::new(
+            Plane::from_vec(7, 6, plane0.to_vec()).unwrap(),
+            plane1,
+            Plane::new(7, 6, 0),
+            7,
+            6,
+            "Lint".to_string(),
+        )
+    }
+
+    fn problems(map: &MapData) -> Vec<(Option<(usize, usize)>, Problem)> {
+        validate(map, Variant::Wolf3d)
+            .into_iter()
+            .map(|x| (x.position, x.problem))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_map() {
+        let map = test_map(&PLANE0, &[((1, 1), 19), ((2, 1), 43), ((1, 2), 44)]);
+
+        assert_eq!(problems(&map), vec![]);
+    }
+
+    #[test]
+    fn test_findings() {
+        let mut plane0 = PLANE0;
+        plane0[3 * 7 + 1] = 94;
+        plane0[3 * 7 + 6] = 400;
+        let map = test_map(
+            &plane0,
+            &[
+                ((2, 1), 19),
+                ((4, 1), 43),
+                ((5, 1), 500),
+                ((0, 2), 36),
+                ((2, 2), 144),
+                ((1, 4), 19),
+            ],
+        );
+
+        assert_eq!(
+            problems(&map),
+            vec![
+                (Some((1, 3)), Problem::DoorWithoutFrame),
+                (Some((6, 3)), Problem::UnknownTile(400)),
+                (Some((5, 1)), Problem::UnknownObject(500)),
+                (Some((0, 2)), Problem::ObjectOnBorder),
+                (Some((2, 2)), Problem::EnemyInWall),
+                (Some((2, 1)), Problem::ExtraPlayerStart),
+                (Some((4, 1)), Problem::KeyBehindOwnDoor(DoorLock::GoldKey)),
+                (Some((1, 3)), Problem::MissingKey(DoorLock::SilverKey)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_start_and_exit() {
+        let mut plane0 = PLANE0;
+        plane0[7 + 3] = 1;
+        let walled = test_map(&plane0, &[((1, 1), 19), ((1, 2), 44)]);
+        plane0[7 + 5] = 1;
+        let no_exit = test_map(&plane0, &[((1, 1), 19), ((1, 2), 44)]);
+        let no_start = test_map(&PLANE0, &[((1, 2), 44)]);
+
+        assert_eq!(
+            problems(&walled),
+            vec![(Some((5, 1)), Problem::UnreachableExit)]
+        );
+        assert_eq!(problems(&no_exit), vec![(None, Problem::NoExit)]);
+        assert_eq!(problems(&no_start), vec![(None, Problem::NoPlayerStart)]);
+        assert_eq!(
+            validate(&walled, Variant::Wolf3d)[0].to_string(),
+            "(5,1) error: exit can't be reached from the player start"
+        );
+        assert_eq!(
+            validate(&no_exit, Variant::Wolf3d)[0].to_string(),
+            "warning: no elevator or victory trigger"
+        );
+    }
+}
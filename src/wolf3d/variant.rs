@@ -0,0 +1,119 @@
+use super::{Wolf3dError, source};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    Wolf3dShareware,
+    Wolf3d,
+    SpearOfDestinyDemo,
+    SpearOfDestiny,
+    NoahsArk,
+}
+
+impl Variant {
+    // Full releases are preferred when a directory holds several data sets.
+    pub const ALL: [Variant; 5] = [
+        Variant::Wolf3d,
+        Variant::SpearOfDestiny,
+        Variant::NoahsArk,
+        Variant::Wolf3dShareware,
+        Variant::SpearOfDestinyDemo,
+    ];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Variant::Wolf3dShareware => "WL1",
+            Variant::Wolf3d => "WL6",
+            Variant::SpearOfDestinyDemo => "SDM",
+            Variant::SpearOfDestiny => "SOD",
+            Variant::NoahsArk => "N3D",
+        }
+    }
+
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        let extension = extension.trim_start_matches('.');
+        Self::ALL
+            .into_iter()
+            .find(|x| x.extension().eq_ignore_ascii_case(extension))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Variant::Wolf3dShareware => "Wolfenstein 3D (shareware)",
+            Variant::Wolf3d => "Wolfenstein 3D",
+            Variant::SpearOfDestinyDemo => "Spear of Destiny (demo)",
+            Variant::SpearOfDestiny => "Spear of Destiny",
+            Variant::NoahsArk => "Super 3-D Noah's Ark",
+        }
+    }
+
+    pub fn maphead_name(&self) -> String {
+        format!("MAPHEAD.{}", self.extension())
+    }
+
+    pub fn gamemaps_name(&self) -> String {
+        format!("GAMEMAPS.{}", self.extension())
+    }
+
+    // TED5 keeps the maps it is editing in MAPTHEAD/MAPTEMP until they are compressed.
+    pub fn mapthead_name(&self) -> String {
+        format!("MAPTHEAD.{}", self.extension())
+    }
+
+    pub fn maptemp_name(&self) -> String {
+        format!("MAPTEMP.{}", self.extension())
+    }
+
+    // The map header and data files found in `path`, preferring GAMEMAPS over MAPTEMP.
+    pub fn map_files(&self, path: &str) -> Option<(String, String)> {
+        [
+            (self.maphead_name(), self.gamemaps_name()),
+            (self.mapthead_name(), self.maptemp_name()),
+        ]
+        .into_iter()
+        .find(|(head, data)| source::exists(path, head) && source::exists(path, data))
+    }
+
+    pub fn detect(path: &str) -> Result<Self, Wolf3dError> {
+        Self::ALL
+            .into_iter()
+            .find(|x| x.map_files(path).is_some())
+            .ok_or(Wolf3dError::MissingFile("MAPHEAD/GAMEMAPS".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_from_extension_ignores_case() {
+        assert_eq!(
+            Variant::from_extension("wl1"),
+            Some(Variant::Wolf3dShareware)
+        );
+        assert_eq!(
+            Variant::from_extension(".Sod"),
+            Some(Variant::SpearOfDestiny)
+        );
+        assert_eq!(Variant::from_extension("N3D"), Some(Variant::NoahsArk));
+        assert_eq!(Variant::from_extension("WL2"), None);
+    }
+
+    #[test]
+    fn test_detect_lowercase_files() {
+        let dir = std::env::temp_dir().join(format!("roguestein-variant-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("maphead.sdm"), []).unwrap();
+        fs::write(dir.join("GameMaps.sdm"), []).unwrap();
+
+        let path = dir.to_str().unwrap();
+        let variant = Variant::detect(path);
+        let gamemaps = source::find_file(path, "GAMEMAPS.SDM");
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(variant.unwrap(), Variant::SpearOfDestinyDemo);
+        assert_eq!(gamemaps.unwrap(), dir.join("GameMaps.sdm"));
+    }
+}
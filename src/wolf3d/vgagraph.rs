@@ -0,0 +1,363 @@
+use super::Wolf3dError;
+
+pub const STRUCTPIC: usize = 0;
+pub const STARTFONT: usize = 1;
+pub const NUMFONT: usize = 2;
+pub const STARTPICS: usize = STARTFONT + NUMFONT;
+
+const HUFFMAN_NODES: usize = 255;
+const HUFFMAN_HEAD: usize = 254;
+const SPARSE: u32 = 0xFF_FFFF;
+
+#[derive(Debug)]
+pub struct Vgagraph {
+    dictionary: Vec<(u16, u16)>,
+    offsets: Vec<Option<u32>>,
+    data: Vec<u8>,
+    pictable: Vec<(u16, u16)>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Picture {
+    pub width: u16,
+    pub height: u16,
+    pub pixels: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Glyph {
+    pub width: u8,
+    pub pixels: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Font {
+    pub height: u16,
+    pub glyphs: Vec<Glyph>,
+}
+
+impl Vgagraph {
+    pub fn parse(vgadict: &[u8], vgahead: &[u8], vgagraph: Vec<u8>) -> Result<Self, Wolf3dError> {
+        if vgadict.len() < HUFFMAN_NODES * 4 {
+            return Err(Wolf3dError::TruncatedFile("VGADICT".to_string()));
+        }
+        let dictionary = vgadict
+            .chunks_exact(4)
+            .take(HUFFMAN_NODES)
+            .map(|x| {
+                (
+                    u16::from_le_bytes([x[0], x[1]]),
+                    u16::from_le_bytes([x[2], x[3]]),
+                )
+            })
+            .collect::<Vec<(u16, u16)>>();
+        if dictionary
+            .iter()
+            .any(|&(a, b)| a as usize >= 256 + HUFFMAN_NODES || b as usize >= 256 + HUFFMAN_NODES)
+        {
+            return Err(Wolf3dError::BadChunk {
+                file: "VGADICT".to_string(),
+                chunk: 0,
+            });
+        }
+
+        // VGAHEAD holds one 3-byte offset per chunk plus the end of the last one.
+        let offsets = vgahead
+            .chunks_exact(3)
+            .map(|x| u32::from_le_bytes([x[0], x[1], x[2], 0]))
+            .map(|x| if x == SPARSE { None } else { Some(x) })
+            .collect::<Vec<Option<u32>>>();
+        if offsets.len() < 2 {
+            return Err(Wolf3dError::TruncatedFile("VGAHEAD".to_string()));
+        }
+
+        let mut result = Self {
+            dictionary,
+            offsets,
+            data: vgagraph,
+            pictable: vec![],
+        };
+
+        result.pictable = result
+            .chunk(STRUCTPIC)?
+            .chunks_exact(4)
+            .map(|x| {
+                (
+                    u16::from_le_bytes([x[0], x[1]]),
+                    u16::from_le_bytes([x[2], x[3]]),
+                )
+            })
+            .collect();
+
+        Ok(result)
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn picture_count(&self) -> usize {
+        self.pictable.len()
+    }
+
+    pub fn picture_size(&self, index: usize) -> Option<(u16, u16)> {
+        self.pictable.get(index).copied()
+    }
+
+    // Compressed chunks start with their expanded length, followed by Huffman coded data.
+    pub fn chunk(&self, chunk: usize) -> Result<Vec<u8>, Wolf3dError> {
+        let bad_chunk = || Wolf3dError::BadChunk {
+            file: "VGAGRAPH".to_string(),
+            chunk,
+        };
+
+        if chunk >= self.chunk_count() {
+            return Err(bad_chunk());
+        }
+        let Some(start) = self.offsets[chunk] else {
+            return Ok(vec![]);
+        };
+        let end = self.offsets[chunk + 1..]
+            .iter()
+            .find_map(|&x| x)
+            .unwrap_or(self.data.len() as u32);
+
+        let data = self
+            .data
+            .get(start as usize..end as usize)
+            .ok_or_else(bad_chunk)?;
+        if data.len() < 4 {
+            return Err(bad_chunk());
+        }
+        let length = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+
+        self.huffman_expand(&data[4..], length)
+            .ok_or_else(bad_chunk)
+    }
+
+    pub fn picture(&self, index: usize) -> Result<Picture, Wolf3dError> {
+        let chunk = STARTPICS + index;
+        let bad_chunk = || Wolf3dError::BadChunk {
+            file: "VGAGRAPH".to_string(),
+            chunk,
+        };
+
+        let (width, height) = self.picture_size(index).ok_or_else(bad_chunk)?;
+        let data = self.chunk(chunk)?;
+        let size = width as usize * height as usize;
+        if data.len() < size || width % 4 != 0 {
+            return Err(bad_chunk());
+        }
+
+        // Pictures are stored as four planes, each holding every fourth column.
+        let quarter = width as usize / 4;
+        let plane_size = quarter * height as usize;
+        let mut pixels = vec![0; size];
+        for y in 0..height as usize {
+            for x in 0..width as usize {
+                pixels[y * width as usize + x] = data[(x % 4) * plane_size + y * quarter + x / 4];
+            }
+        }
+
+        Ok(Picture {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    pub fn font(&self, index: usize) -> Result<Font, Wolf3dError> {
+        let chunk = STARTFONT + index;
+        let bad_chunk = || Wolf3dError::BadChunk {
+            file: "VGAGRAPH".to_string(),
+            chunk,
+        };
+        if index >= NUMFONT {
+            return Err(bad_chunk());
+        }
+
+        let data = self.chunk(chunk)?;
+        if data.len() < 2 + 256 * 3 {
+            return Err(bad_chunk());
+        }
+        let height = u16::from_le_bytes([data[0], data[1]]);
+
+        let mut glyphs = vec![];
+        for i in 0..256 {
+            let location = u16::from_le_bytes([data[2 + i * 2], data[3 + i * 2]]) as usize;
+            let width = data[2 + 512 + i];
+            let size = width as usize * height as usize;
+            let pixels = match size {
+                0 => vec![],
+                _ => data
+                    .get(location..location + size)
+                    .ok_or_else(bad_chunk)?
+                    .to_vec(),
+            };
+            glyphs.push(Glyph { width, pixels });
+        }
+
+        Ok(Font { height, glyphs })
+    }
+
+    // Bits are read least significant first; node values below 256 are output bytes. Every
+    // output byte takes at least one bit, which bounds the length a chunk can claim.
+    fn huffman_expand(&self, compressed: &[u8], length: usize) -> Option<Vec<u8>> {
+        if length > compressed.len().saturating_mul(8) {
+            return None;
+        }
+        let mut result = Vec::with_capacity(length);
+        let mut node = HUFFMAN_HEAD;
+
+        for &byte in compressed {
+            for bit in 0..8 {
+                if result.len() >= length {
+                    return Some(result);
+                }
+
+                let (zero, one) = self.dictionary[node];
+                let value = if byte & (1 << bit) == 0 { zero } else { one };
+                if value < 256 {
+                    result.push(value as u8);
+                    node = HUFFMAN_HEAD;
+                } else {
+                    node = value as usize - 256;
+                }
+            }
+        }
+
+        if result.len() < length {
+            return None;
+        }
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A complete tree where every byte is coded as its own eight bits, high bit first.
+    fn test_dictionary() -> Vec<u8> {
+        fn build(prefix: u16, depth: u32, nodes: &mut Vec<(u16, u16)>) -> u16 {
+            if depth == 8 {
+                return prefix;
+            }
+            let zero = build(prefix << 1, depth + 1, nodes);
+            let one = build((prefix << 1) | 1, depth + 1, nodes);
+            nodes.push((zero, one));
+            255 + nodes.len() as u16
+        }
+
+        let mut nodes = vec![];
+        build(0, 0, &mut nodes);
+        nodes
+            .iter()
+            .flat_map(|&(a, b)| [a.to_le_bytes(), b.to_le_bytes()].concat())
+            .collect()
+    }
+
+    fn compress(data: &[u8]) -> Vec<u8> {
+        let mut result = (data.len() as u32).to_le_bytes().to_vec();
+        let bits = data
+            .iter()
+            .flat_map(|&x| (0..8).rev().map(move |bit| (x >> bit) & 1))
+            .collect::<Vec<u8>>();
+        for chunk in bits.chunks(8) {
+            result.push(
+                chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0, |acc, (i, &bit)| acc | (bit << i)),
+            );
+        }
+        result
+    }
+
+    fn test_vgagraph() -> Vgagraph {
+        let pictable = vec![4, 0, 2, 0];
+
+        let mut font = vec![1, 0];
+        font.extend((0..256).flat_map(|x| if x == 65 { 770u16 } else { 0 }.to_le_bytes()));
+        font.extend((0..256).map(|x| if x == 65 { 2 } else { 0 }));
+        font.extend([15, 0]);
+
+        // Planes hold columns 0, 1, 2 and 3 of both rows.
+        let picture = vec![1, 5, 2, 6, 3, 7, 4, 8];
+
+        let chunks = [Some(pictable), Some(font), None, Some(picture)];
+        let mut vgahead = vec![];
+        let mut vgagraph = vec![];
+        for chunk in chunks {
+            let offset = match chunk {
+                Some(chunk) => {
+                    let offset = vgagraph.len() as u32;
+                    vgagraph.extend(compress(&chunk));
+                    offset
+                }
+                None => SPARSE,
+            };
+            vgahead.extend(&offset.to_le_bytes()[..3]);
+        }
+        vgahead.extend(&(vgagraph.len() as u32).to_le_bytes()[..3]);
+
+        Vgagraph::parse(&test_dictionary(), &vgahead, vgagraph).unwrap()
+    }
+
+    #[test]
+    fn test_huffman_expand_pictable() {
+        let vgagraph = test_vgagraph();
+
+        assert_eq!(vgagraph.chunk_count(), 4);
+        assert_eq!(vgagraph.picture_count(), 1);
+        assert_eq!(vgagraph.picture_size(0), Some((4, 2)));
+        assert_eq!(vgagraph.chunk(STARTFONT + 1).unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn test_picture_planar_to_linear() {
+        let vgagraph = test_vgagraph();
+
+        let picture = vgagraph.picture(0).unwrap();
+
+        assert_eq!(picture.pixels, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_font_glyphs() {
+        let vgagraph = test_vgagraph();
+
+        let font = vgagraph.font(0).unwrap();
+
+        assert_eq!(font.height, 1);
+        assert_eq!(font.glyphs.len(), 256);
+        assert_eq!(
+            font.glyphs[65],
+            Glyph {
+                width: 2,
+                pixels: vec![15, 0]
+            }
+        );
+        assert!(vgagraph.font(NUMFONT).is_err());
+    }
+
+    #[test]
+    fn test_oversized_chunk_length() {
+        let mut vgagraph = test_vgagraph();
+        let start = vgagraph.offsets[STARTPICS].unwrap() as usize;
+        vgagraph.data[start..start + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        assert!(matches!(
+            vgagraph.picture(0),
+            Err(Wolf3dError::BadChunk { .. })
+        ));
+    }
+
+    #[test]
+    fn test_truncated_dictionary() {
+        assert!(matches!(
+            Vgagraph::parse(&[0; 10], &[0; 6], vec![]),
+            Err(Wolf3dError::TruncatedFile(_))
+        ));
+    }
+}
@@ -0,0 +1,325 @@
+use std::io::{BufReader, Read};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use super::Wolf3dError;
+
+pub const PAGE_WIDTH: usize = 64;
+pub const PAGE_HEIGHT: usize = 64;
+
+#[derive(Debug)]
+pub struct Vswap {
+    pub sprite_start: u16,
+    pub sound_start: u16,
+    offsets: Vec<u32>,
+    lengths: Vec<u16>,
+    data: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Wall {
+    pub pixels: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Sprite {
+    pub left: u16,
+    pub right: u16,
+    pub pixels: Vec<Option<u8>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SoundInfo {
+    pub start_page: u16,
+    pub length: u16,
+}
+
+impl Vswap {
+    pub fn parse(data: Vec<u8>) -> Result<Self, Wolf3dError> {
+        let mut buf = BufReader::new(&data[..]);
+        let truncated = |_| Wolf3dError::TruncatedFile("VSWAP".to_string());
+
+        let chunks = buf.read_u16::<LittleEndian>().map_err(truncated)?;
+        let sprite_start = buf.read_u16::<LittleEndian>().map_err(truncated)?;
+        let sound_start = buf.read_u16::<LittleEndian>().map_err(truncated)?;
+
+        let mut offsets = vec![];
+        for _ in 0..chunks {
+            offsets.push(buf.read_u32::<LittleEndian>().map_err(truncated)?);
+        }
+
+        let mut lengths = vec![];
+        for _ in 0..chunks {
+            lengths.push(buf.read_u16::<LittleEndian>().map_err(truncated)?);
+        }
+
+        if sprite_start > sound_start || sound_start > chunks {
+            return Err(Wolf3dError::TruncatedFile("VSWAP".to_string()));
+        }
+
+        Ok(Self {
+            sprite_start,
+            sound_start,
+            offsets,
+            lengths,
+            data,
+        })
+    }
+
+    pub fn from_reader(mut reader: impl Read) -> Result<Self, Wolf3dError> {
+        let mut data = vec![];
+        reader.read_to_end(&mut data)?;
+        Self::parse(data)
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn wall_count(&self) -> usize {
+        self.sprite_start as usize
+    }
+
+    pub fn sprite_count(&self) -> usize {
+        (self.sound_start - self.sprite_start) as usize
+    }
+
+    // Sparse data sets (the shareware release) leave unused chunks at offset zero.
+    pub fn chunk(&self, chunk: usize) -> Result<&[u8], Wolf3dError> {
+        let bad_chunk = || Wolf3dError::BadChunk {
+            file: "VSWAP".to_string(),
+            chunk,
+        };
+
+        let offset = *self.offsets.get(chunk).ok_or_else(bad_chunk)? as usize;
+        if offset == 0 {
+            return Ok(&[]);
+        }
+
+        let length = self.lengths[chunk] as usize;
+        self.data.get(offset..offset + length).ok_or_else(bad_chunk)
+    }
+
+    pub fn wall(&self, index: usize) -> Result<Wall, Wolf3dError> {
+        let chunk = self.page_chunk(index, 0, self.wall_count())?;
+        let data = self.chunk(chunk)?;
+        if data.len() < PAGE_WIDTH * PAGE_HEIGHT {
+            return Err(Wolf3dError::BadChunk {
+                file: "VSWAP".to_string(),
+                chunk,
+            });
+        }
+
+        // Walls are stored column by column.
+        let mut pixels = vec![0; PAGE_WIDTH * PAGE_HEIGHT];
+        for x in 0..PAGE_WIDTH {
+            for y in 0..PAGE_HEIGHT {
+                pixels[y * PAGE_WIDTH + x] = data[x * PAGE_HEIGHT + y];
+            }
+        }
+
+        Ok(Wall { pixels })
+    }
+
+    pub fn sprite(&self, index: usize) -> Result<Sprite, Wolf3dError> {
+        let chunk = self.page_chunk(index, self.wall_count(), self.sprite_count())?;
+        let data = self.chunk(chunk)?;
+        let bad_chunk = || Wolf3dError::BadChunk {
+            file: "VSWAP".to_string(),
+            chunk,
+        };
+        let word = |offset: usize| {
+            data.get(offset..offset + 2)
+                .map(|x| u16::from_le_bytes([x[0], x[1]]))
+                .ok_or_else(bad_chunk)
+        };
+
+        let left = word(0)?;
+        let right = word(2)?;
+        if left > right || right as usize >= PAGE_WIDTH {
+            return Err(bad_chunk());
+        }
+
+        let mut pixels = vec![None; PAGE_WIDTH * PAGE_HEIGHT];
+        for x in left..=right {
+            let mut command = word(4 + (x - left) as usize * 2)? as usize;
+
+            // Each post is (end * 2, pixel offset, start * 2) and the list ends with a zero.
+            loop {
+                let end = word(command)? as usize / 2;
+                if end == 0 {
+                    break;
+                }
+                let source = word(command + 2)? as i16 as isize;
+                let start = word(command + 4)? as usize / 2;
+                if start > end || end > PAGE_HEIGHT {
+                    return Err(bad_chunk());
+                }
+
+                for y in start..end {
+                    let pixel = usize::try_from(source + y as isize)
+                        .ok()
+                        .and_then(|x| data.get(x))
+                        .ok_or_else(bad_chunk)?;
+                    pixels[y * PAGE_WIDTH + x as usize] = Some(*pixel);
+                }
+                command += 6;
+            }
+        }
+
+        Ok(Sprite {
+            left,
+            right,
+            pixels,
+        })
+    }
+
+    // The last chunk lists (start page, length in bytes) for every digitized sound.
+    pub fn sounds(&self) -> Result<Vec<SoundInfo>, Wolf3dError> {
+        let chunk = self.chunk_count().saturating_sub(1);
+        if chunk < self.sound_start as usize {
+            return Ok(vec![]);
+        }
+
+        Ok(self
+            .chunk(chunk)?
+            .chunks_exact(4)
+            .map(|x| SoundInfo {
+                start_page: u16::from_le_bytes([x[0], x[1]]),
+                length: u16::from_le_bytes([x[2], x[3]]),
+            })
+            .collect())
+    }
+
+    pub fn sound(&self, index: usize) -> Result<Vec<u8>, Wolf3dError> {
+        let sounds = self.sounds()?;
+        let info = sounds.get(index).ok_or(Wolf3dError::BadChunk {
+            file: "VSWAP".to_string(),
+            chunk: self.chunk_count().saturating_sub(1),
+        })?;
+
+        // A sound spans as many consecutive pages as its length needs.
+        let mut result = vec![];
+        let mut chunk = self.sound_start as usize + info.start_page as usize;
+        while result.len() < info.length as usize {
+            let data = if chunk + 1 < self.chunk_count() {
+                self.chunk(chunk)?
+            } else {
+                &[]
+            };
+            if data.is_empty() {
+                return Err(Wolf3dError::BadChunk {
+                    file: "VSWAP".to_string(),
+                    chunk,
+                });
+            }
+            result.extend_from_slice(data);
+            chunk += 1;
+        }
+        result.truncate(info.length as usize);
+
+        Ok(result)
+    }
+
+    fn page_chunk(&self, index: usize, first: usize, count: usize) -> Result<usize, Wolf3dError> {
+        if index >= count {
+            return Err(Wolf3dError::BadChunk {
+                file: "VSWAP".to_string(),
+                chunk: first + index,
+            });
+        }
+
+        Ok(first + index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_vswap() -> Vec<u8> {
+        let mut wall = vec![0; PAGE_WIDTH * PAGE_HEIGHT];
+        wall[PAGE_HEIGHT + 2] = 7;
+
+        let sprite = vec![
+            0x01, 0x00, 0x01, 0x00, 0x06, 0x00, 0x06, 0x00, 0x0D, 0x00, 0x02, 0x00, 0x00, 0x00,
+            0x11, 0x22,
+        ];
+        let sound = vec![0x80; 5];
+        let info = vec![0x00, 0x00, 0x05, 0x00];
+
+        let chunks = [wall, sprite, sound, info];
+        let mut data = vec![];
+        data.extend_from_slice(&4u16.to_le_bytes());
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&2u16.to_le_bytes());
+        let mut offset = 6 + chunks.len() * 6;
+        for chunk in chunks.iter() {
+            data.extend_from_slice(&(offset as u32).to_le_bytes());
+            offset += chunk.len();
+        }
+        for chunk in chunks.iter() {
+            data.extend_from_slice(&(chunk.len() as u16).to_le_bytes());
+        }
+        for chunk in chunks.iter() {
+            data.extend_from_slice(chunk);
+        }
+
+        data
+    }
+
+    #[test]
+    fn test_parse_vswap() {
+        let vswap = Vswap::parse(test_vswap()).unwrap();
+
+        assert_eq!(vswap.chunk_count(), 4);
+        assert_eq!(vswap.wall_count(), 1);
+        assert_eq!(vswap.sprite_count(), 1);
+    }
+
+    #[test]
+    fn test_decode_wall_is_row_major() {
+        let vswap = Vswap::parse(test_vswap()).unwrap();
+
+        let wall = vswap.wall(0).unwrap();
+
+        assert_eq!(wall.pixels[2 * PAGE_WIDTH + 1], 7);
+        assert!(vswap.wall(1).is_err());
+    }
+
+    #[test]
+    fn test_decode_sprite_posts() {
+        let vswap = Vswap::parse(test_vswap()).unwrap();
+
+        let sprite = vswap.sprite(0).unwrap();
+
+        assert_eq!(sprite.pixels[PAGE_WIDTH + 1], Some(0x11));
+        assert_eq!(sprite.pixels[2 * PAGE_WIDTH + 1], Some(0x22));
+        assert_eq!(sprite.pixels.iter().filter(|x| x.is_some()).count(), 2);
+    }
+
+    #[test]
+    fn test_read_sounds() {
+        let vswap = Vswap::parse(test_vswap()).unwrap();
+
+        assert_eq!(
+            vswap.sounds().unwrap(),
+            vec![SoundInfo {
+                start_page: 0,
+                length: 5
+            }]
+        );
+        assert_eq!(vswap.sound(0).unwrap(), vec![0x80; 5]);
+    }
+
+    #[test]
+    fn test_parse_truncated_vswap() {
+        let mut data = test_vswap();
+        data.truncate(10);
+
+        assert!(matches!(
+            Vswap::parse(data),
+            Err(Wolf3dError::TruncatedFile(_))
+        ));
+    }
+}